@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::parser::{unquote_json_string, Error, ErrorKind, Span, Value};
+
+/// The key of the metadata block in a Berry lockfile.
+pub const METADATA_KEY: &str = "__metadata";
+
+struct SignificantLine<'t> {
+    /// The byte offset of the content in the input.
+    offset: usize,
+    indent: usize,
+    content: &'t str,
+}
+
+/// Parse the input Yarn Berry (v2+) lock data.
+///
+/// Berry lockfiles are written in the YAML subset produced by [`@yarnpkg/parsers`](https://github.com/yarnpkg/berry/blob/master/packages/yarnpkg-parsers/sources/syml.ts).
+/// Like Berry itself, every scalar is kept as a string. Top-level keys listing several
+/// descriptors, e.g. `"a@npm:^1.0.0, a@npm:^1.1.0":`, are split so the result has the same shape
+/// as [`crate::parser::parse`].
+///
+/// # Errors
+/// - [`Error`]: When parsing failed
+pub fn parse(input: &[u8]) -> Result<Value, Error> {
+    let text = std::str::from_utf8(input).map_err(|e| error(input, e.valid_up_to(), 1, ErrorKind::InvalidUtf8))?;
+    let bom = if text.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 };
+    let mut lines = vec![];
+    let mut offset = bom;
+    for raw in text[bom..].split_inclusive('\n') {
+        let line_offset = offset;
+        offset += raw.len();
+        let raw = raw.trim_end_matches(['\n', '\r']);
+        let content = raw.trim_start_matches(' ');
+        if content.trim().is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = raw.len() - content.len();
+        if content.starts_with('\t') {
+            return Err(error(input, line_offset + indent, 1, ErrorKind::InvalidCharacter));
+        }
+        lines.push(SignificantLine { offset: line_offset + indent, indent, content: content.trim_end() });
+    }
+    let mut ptr = 0;
+    let Value::Object(map) = parse_mapping(input, &lines, &mut ptr, 0)? else { unreachable!() };
+    if let Some(line) = lines.get(ptr) {
+        return Err(error(input, line.offset, line.content.len(), ErrorKind::UnexpectedIndent { expected: 0, found: line.indent }));
+    }
+    let mut result = HashMap::new();
+    for (key, value) in map {
+        if key == METADATA_KEY {
+            result.insert(key, value);
+            continue;
+        }
+        for descriptor in key.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            result.insert(descriptor.to_string(), value.clone());
+        }
+    }
+    Ok(Value::Object(result))
+}
+
+fn error(input: &[u8], start: usize, len: usize, kind: ErrorKind) -> Error {
+    Error::at(input, Span { start, end: start + len }, kind)
+}
+
+fn parse_mapping(input: &[u8], lines: &[SignificantLine], ptr: &mut usize, indent: usize) -> Result<Value, Error> {
+    let mut map = HashMap::new();
+    while let Some(line) = lines.get(*ptr) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent {
+            return Err(error(input, line.offset, line.content.len(), ErrorKind::UnexpectedIndent { expected: indent, found: line.indent }));
+        }
+        let (key, rest) = parse_key(input, line)?;
+        *ptr += 1;
+        let value = if rest.is_empty() {
+            match lines.get(*ptr) {
+                Some(next) if next.indent > indent => parse_mapping(input, lines, ptr, next.indent)?,
+                _ => Value::Null,
+            }
+        } else {
+            Value::String(Rc::new(parse_scalar(input, line, rest)?))
+        };
+        map.insert(key, value);
+    }
+    Ok(Value::Object(map))
+}
+
+fn parse_key<'t>(input: &[u8], line: &SignificantLine<'t>) -> Result<(String, &'t str), Error> {
+    let content = line.content;
+    let err = |kind| error(input, line.offset, content.len(), kind);
+    let (key, rest) = match content.as_bytes()[0] {
+        b'"' | b'\'' => {
+            let len = measure_quoted(content).ok_or_else(|| err(ErrorKind::InvalidString))?;
+            (unquote(&content[..len]).ok_or_else(|| err(ErrorKind::InvalidString))?, &content[len..])
+        }
+        _ => {
+            let len = content.find(": ").unwrap_or_else(|| content.strip_suffix(':').map_or(content.len(), str::len));
+            (content[..len].to_string(), &content[len..])
+        }
+    };
+    let Some(rest) = rest.strip_prefix(':') else {
+        return Err(err(ErrorKind::ExpectedColon));
+    };
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return Err(err(ErrorKind::ExpectedColon));
+    }
+    if key.is_empty() {
+        return Err(err(ErrorKind::ExpectedKey));
+    }
+    Ok((key, rest.trim_start()))
+}
+
+fn parse_scalar(input: &[u8], line: &SignificantLine, s: &str) -> Result<String, Error> {
+    let err = |kind| error(input, line.offset + line.content.len() - s.len(), s.len(), kind);
+    match s.as_bytes()[0] {
+        b'"' | b'\'' => {
+            let len = measure_quoted(s).ok_or_else(|| err(ErrorKind::InvalidString))?;
+            if len != s.len() {
+                return Err(err(ErrorKind::TrailingCharacters));
+            }
+            unquote(s).ok_or_else(|| err(ErrorKind::InvalidString))
+        }
+        _ => Ok(s.to_string()),
+    }
+}
+
+/// Measure a double- or single-quoted string at the start of the input, including the quotes.
+fn measure_quoted(input: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let quote = bytes[0];
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote == b'"' => i += 1,
+            b'\'' if quote == b'\'' && bytes.get(i + 1) == Some(&b'\'') => i += 1,
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn unquote(input: &str) -> Option<String> {
+    if input.starts_with('"') {
+        unquote_json_string(input.as_bytes())
+    } else {
+        Some(input[1..input.len() - 1].replace("''", "'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'v>(value: &'v Value, path: &[&str]) -> &'v Value {
+        path.iter().fold(value, |v, k| match v {
+            Value::Object(map) => &map[*k],
+            _ => panic!("not an object"),
+        })
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(Rc::new(s.to_string()))
+    }
+
+    #[test]
+    fn parse_berry() {
+        let v = parse(include_bytes!("test.berry.lock")).unwrap();
+        assert_eq!(&string("8"), get(&v, &["__metadata", "version"]));
+        assert_eq!(&string("10c0"), get(&v, &["__metadata", "cacheKey"]));
+        assert_eq!(&string("7.24.7"), get(&v, &["@babel/code-frame@npm:^7.0.0", "version"]));
+        assert_eq!(get(&v, &["@babel/code-frame@npm:^7.0.0"]), get(&v, &["@babel/code-frame@npm:^7.24.7"]));
+        assert_eq!(&string("npm:^1.0.0"), get(&v, &["@babel/code-frame@npm:^7.0.0", "dependencies", "picocolors"]));
+        assert_eq!(&string("true"), get(&v, &["typescript@patch:typescript@npm%3A^5.0.0#optional!builtin<compat/typescript>", "peerDependenciesMeta", "@types/node", "optional"]));
+        assert_eq!(&string("soft"), get(&v, &["my-app@workspace:.", "linkType"]));
+    }
+
+    #[test]
+    fn parse_quoted_scalars() {
+        let v = parse(b"a:\n  b: \"x\\\"y\"\n  c: 'it''s'\n  d: plain value\n").unwrap();
+        assert_eq!(&string("x\"y"), get(&v, &["a", "b"]));
+        assert_eq!(&string("it's"), get(&v, &["a", "c"]));
+        assert_eq!(&string("plain value"), get(&v, &["a", "d"]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let e = parse(b"a:\n  b: c\n    d: e\n").unwrap_err();
+        assert_eq!((3, ErrorKind::UnexpectedIndent { expected: 2, found: 4 }), (e.line, e.kind));
+        let e = parse(b"a:\n  b c\n").unwrap_err();
+        assert_eq!((2, ErrorKind::ExpectedColon), (e.line, e.kind));
+        let e = parse(b"a:\n  b: \"c\n").unwrap_err();
+        assert_eq!((2, ErrorKind::InvalidString), (e.line, e.kind));
+        assert_eq!(Span { start: 8, end: 10 }, e.span);
+        let e = parse(b"\xef\xbb\xbfa:\r\n\tb: c\r\n").unwrap_err();
+        assert_eq!((2, 0, Span { start: 7, end: 8 }), (e.line, e.col, e.span));
+    }
+}
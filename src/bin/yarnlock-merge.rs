@@ -0,0 +1,57 @@
+//! A git merge driver for yarn v1 lockfiles.
+//!
+//! Register it for `yarn.lock` with:
+//!
+//! ```text
+//! git config merge.yarnlock.name "yarn.lock merge driver"
+//! git config merge.yarnlock.driver "yarnlock-merge %O %A %B"
+//! echo "yarn.lock merge=yarnlock" >> .gitattributes
+//! ```
+//!
+//! The merged lockfile is written to `%A`. Exits with `0` on a clean merge, `1` when some
+//! descriptors conflict, which are then written between conflict markers, and `2` on errors.
+use std::error::Error;
+use std::fs;
+use std::process::ExitCode;
+
+use yarnlock::detect_format;
+use yarnlock::format::Dialect;
+use yarnlock::lockfile::{self, Lockfile};
+use yarnlock::merge::merge;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [base, ours, theirs] = args.as_slice() else {
+        eprintln!("usage: yarnlock-merge <base> <ours> <theirs>");
+        return ExitCode::from(2);
+    };
+    match run(base, ours, theirs) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("yarnlock-merge: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(base: &str, ours: &str, theirs: &str) -> Result<bool, Box<dyn Error>> {
+    let result = merge(&read(base)?, &read(ours)?, &read(theirs)?);
+    for conflict in &result.conflicts {
+        eprintln!("yarnlock-merge: conflict in {}: {} (ours) vs {} (theirs)", conflict.descriptor, conflict.ours.version, conflict.theirs.version);
+    }
+    fs::write(ours, result.to_string())?;
+    Ok(result.is_clean())
+}
+
+fn read(path: &str) -> Result<Lockfile, Box<dyn Error>> {
+    let input = fs::read(path)?;
+    // the base is empty when both sides added the lockfile
+    if input.trim_ascii().is_empty() {
+        return Ok(Lockfile::default());
+    }
+    match detect_format(&input) {
+        Some(format) if format.dialect != Dialect::YarnV1 => Err(lockfile::Error::UnsupportedFormat(format).into()),
+        _ => Ok(Lockfile::parse(&input)?),
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::mem;
+use std::rc::Rc;
+
+use crate::lexer::Lexer;
+use crate::parser::{unquote_string, version_match, Error, ErrorKind, Span, TokenKind, Value};
+use crate::tokens::Token;
+
+const BOM: &str = "\u{feff}";
+
+/// A lossless syntax tree of yarn lock data.
+///
+/// Unlike [`crate::parser::parse`], the document keeps comments, blank lines, key order, quoting
+/// style and line endings, so `to_string()` reproduces the input exactly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub(crate) bom: bool,
+    pub(crate) nodes: Vec<Node>,
+    /// The blank and comment lines after the last node.
+    pub(crate) trailing: Vec<Trivia>,
+}
+
+/// A key line and, for a mapping, its nested lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// The blank and comment lines before this line.
+    pub(crate) leading: Vec<Trivia>,
+    pub(crate) indent: usize,
+    /// The line text after the indentation, without the line ending.
+    pub(crate) raw: String,
+    pub(crate) eol: LineEnding,
+    pub(crate) keys: Vec<String>,
+    pub(crate) value: NodeValue,
+}
+
+/// The value of a [`Node`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeValue {
+    /// A string, number or boolean written on the key line.
+    Scalar(Value),
+    /// The nested lines of a key line ending with a colon.
+    Mapping(Vec<Node>),
+}
+
+/// A blank or comment line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trivia {
+    /// The line text, without the line ending.
+    pub(crate) raw: String,
+    pub(crate) eol: LineEnding,
+}
+
+/// The line ending of a line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+    /// The last line of input without a line ending.
+    None,
+}
+
+impl LineEnding {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
+            Self::None => "",
+        }
+    }
+}
+
+enum Line {
+    Trivia(Trivia),
+    Node { offset: usize, indent: usize, raw: String, eol: LineEnding, keys: Vec<String>, value: Option<Value> },
+}
+
+impl Document {
+    /// Parse the input yarn lock data into a lossless syntax tree.
+    ///
+    /// Accepts the same input as [`crate::parser::parse`], except that every key line must have
+    /// the indentation of its mapping, and a leading byte order mark is kept.
+    ///
+    /// # Errors
+    /// - [`Error`]: When parsing failed
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let bom = input.starts_with(BOM.as_bytes());
+        let mut lines = vec![];
+        let mut offset = if bom { BOM.len() } else { 0 };
+        while offset < input.len() {
+            let len = input[offset..].iter().position(|&b| b == b'\n' || b == b'\r').unwrap_or(input.len() - offset);
+            let eol = match &input[offset + len..] {
+                [b'\r', b'\n', ..] => LineEnding::CrLf,
+                [b'\r', ..] => LineEnding::Cr,
+                [b'\n', ..] => LineEnding::Lf,
+                _ => LineEnding::None,
+            };
+            lines.push(parse_line(input, offset, len, eol)?);
+            offset += len + eol.as_str().len();
+        }
+        let mut ptr = 0;
+        let mut trivia = vec![];
+        let nodes = build(input, &mut lines, &mut ptr, 0, &mut trivia)?;
+        Ok(Self { bom, nodes, trailing: trivia })
+    }
+
+    /// The top-level nodes, in input order.
+    #[must_use]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Get the top-level node having the key, e.g. a descriptor.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        find(&self.nodes, key)
+    }
+
+    /// Convert the document into the value returned by [`crate::parser::parse`].
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        mapping_value(&self.nodes)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.bom {
+            f.write_str(BOM)?;
+        }
+        for node in &self.nodes {
+            write!(f, "{node}")?;
+        }
+        for trivia in &self.trailing {
+            write!(f, "{trivia}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// The unquoted keys of the line, several for a line like `"a@^1.0.0", "a@^1.1.0":`.
+    #[must_use]
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// The number of spaces before the keys.
+    #[must_use]
+    pub const fn indent(&self) -> usize {
+        self.indent
+    }
+
+    #[must_use]
+    pub const fn value(&self) -> &NodeValue {
+        &self.value
+    }
+
+    /// The nested nodes, empty for a scalar.
+    #[must_use]
+    pub fn children(&self) -> &[Self] {
+        match &self.value {
+            NodeValue::Mapping(nodes) => nodes,
+            NodeValue::Scalar(_) => &[],
+        }
+    }
+
+    /// Get the nested node having the key.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        find(self.children(), key)
+    }
+
+    /// The blank and comment lines before this line, without line endings.
+    pub fn leading_trivia(&self) -> impl Iterator<Item = &str> {
+        self.leading.iter().map(|t| t.raw.as_str())
+    }
+
+    /// Convert the node value into the value returned by [`crate::parser::parse`].
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        match &self.value {
+            NodeValue::Scalar(v) => v.clone(),
+            NodeValue::Mapping(nodes) => mapping_value(nodes),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{trivia}")?;
+        }
+        write!(f, "{:indent$}{}{}", "", self.raw, self.eol.as_str(), indent = self.indent)?;
+        for node in self.children() {
+            write!(f, "{node}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.raw, self.eol.as_str())
+    }
+}
+
+fn find<'n>(nodes: &'n [Node], key: &str) -> Option<&'n Node> {
+    // later lines win, like in a parsed object
+    nodes.iter().rev().find(|n| n.keys.iter().any(|k| k == key))
+}
+
+fn mapping_value(nodes: &[Node]) -> Value {
+    let mut map = HashMap::new();
+    for node in nodes {
+        let value = node.to_value();
+        for key in &node.keys {
+            map.insert(key.clone(), value.clone());
+        }
+    }
+    Value::Object(map)
+}
+
+/// Tokenize a single line with the lexer and check it has the shape of a key line.
+fn parse_line(input: &[u8], offset: usize, len: usize, eol: LineEnding) -> Result<Line, Error> {
+    let text = &input[offset..offset + len];
+    let error = |span: Span, kind| Error::at(input, Span { start: offset + span.start, end: offset + span.end }, kind);
+    let raw = std::str::from_utf8(text).map_err(|e| error(Span { start: e.valid_up_to(), end: e.valid_up_to() + 1 }, ErrorKind::InvalidUtf8))?;
+    let mut lexer = Lexer::new(text);
+    let mut indent = 0;
+    let mut tokens = vec![];
+    while let Some(tk) = lexer.next() {
+        let tk = tk.map_err(|e| error(e.span, e.kind))?;
+        match tk.token {
+            Token::Indent(n) => indent = n,
+            Token::Comment(cm) => {
+                if let Some(v) = version_match(cm).filter(|&v| v > 1) {
+                    return Err(error(lexer.span(), ErrorKind::UnsupportedVersion(v)));
+                }
+            }
+            token => tokens.push((token, lexer.span())),
+        }
+    }
+    // the lexer always ends with an Eof token
+    if tokens.len() == 1 {
+        return Ok(Line::Trivia(Trivia { raw: raw.to_string(), eol }));
+    }
+    let mut tokens = tokens.into_iter().peekable();
+    let mut keys = vec![];
+    loop {
+        let (token, span) = tokens.next().unwrap();
+        let Token::String(s) = token else {
+            let kind = if keys.is_empty() { ErrorKind::UnexpectedToken(TokenKind::from(&token)) } else { ErrorKind::ExpectedString };
+            return Err(error(span, kind));
+        };
+        let key = unquote_string(s).map_err(|kind| error(span, kind))?;
+        if key.is_empty() {
+            return Err(error(span, ErrorKind::ExpectedKey));
+        }
+        keys.push(key.into_owned());
+        if tokens.next_if(|(t, _)| *t == Token::Comma).is_none() {
+            break;
+        }
+    }
+    let was_colon = tokens.next_if(|(t, _)| *t == Token::Colon).is_some();
+    let value = match tokens.next_if(|(t, _)| matches!(t, Token::String(_) | Token::Number(_) | Token::Bool(_))) {
+        Some((Token::String(s), span)) => Some(Value::String(Rc::new(unquote_string(s).map_err(|kind| error(span, kind))?.into_owned()))),
+        Some((Token::Number(n), _)) => Some(Value::Number(n)),
+        Some((Token::Bool(b), _)) => Some(Value::Boolean(b)),
+        _ => None,
+    };
+    let (token, span) = tokens.next().unwrap();
+    if token != Token::Eof || (value.is_none() && !was_colon) {
+        return Err(error(span, ErrorKind::UnexpectedToken(TokenKind::from(&token))));
+    }
+    Ok(Line::Node { offset: offset + indent, indent, raw: raw[indent..].to_string(), eol, keys, value })
+}
+
+fn build(input: &[u8], lines: &mut [Line], ptr: &mut usize, indent: usize, trivia: &mut Vec<Trivia>) -> Result<Vec<Node>, Error> {
+    let mut nodes = vec![];
+    while let Some(line) = lines.get_mut(*ptr) {
+        let Line::Node { offset, indent: n, raw, eol, keys, value } = line else {
+            let Line::Trivia(t) = mem::replace(line, Line::Trivia(Trivia { raw: String::new(), eol: LineEnding::None })) else { unreachable!() };
+            trivia.push(t);
+            *ptr += 1;
+            continue;
+        };
+        if *n < indent {
+            break;
+        }
+        if *n > indent {
+            let span = Span { start: *offset - (*n - indent), end: *offset };
+            return Err(Error::at(input, span, ErrorKind::UnexpectedIndent { expected: indent, found: *n }));
+        }
+        let (raw, eol, keys, value) = (mem::take(raw), *eol, mem::take(keys), value.take());
+        *ptr += 1;
+        let leading = mem::take(trivia);
+        let value = match value {
+            Some(v) => NodeValue::Scalar(v),
+            None => NodeValue::Mapping(build(input, lines, ptr, indent + 2, trivia)?),
+        };
+        nodes.push(Node { leading, indent, raw, eol, keys, value });
+    }
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    fn round_trip(input: &[u8]) -> Document {
+        let doc = Document::parse(input).unwrap();
+        assert_eq!(String::from_utf8_lossy(input), doc.to_string());
+        doc
+    }
+
+    #[test]
+    fn round_trip_lockfiles() {
+        for input in [&include_bytes!("test.lock.0")[..], include_bytes!("test.lock.1"), include_bytes!("test.lock.2")] {
+            let doc = round_trip(input);
+            assert_eq!(parse(input).unwrap(), doc.to_value());
+            let crlf = String::from_utf8_lossy(input).replace('\n', "\r\n");
+            assert_eq!(parse(crlf.as_bytes()).unwrap(), round_trip(crlf.as_bytes()).to_value());
+        }
+    }
+
+    #[test]
+    fn keeps_comments_and_quoting() {
+        let input = "\u{feff}# header\r\n\n\"a@^1\", a@^1.2:\n  # why\n  version \"1.2.0\"  # pinned\n  dependencies:\n    b: \"^2\"\n\n\n  optional true\n\nc \"1\"\n# the end";
+        let doc = round_trip(input.as_bytes());
+        let a = doc.get("a@^1.2").unwrap();
+        assert_eq!(["a@^1", "a@^1.2"], a.keys());
+        assert_eq!(vec!["# header", ""], doc.nodes()[0].leading_trivia().collect::<Vec<_>>());
+        assert_eq!(vec!["  # why"], a.children()[0].leading_trivia().collect::<Vec<_>>());
+        assert_eq!(&NodeValue::Scalar(Value::String(Rc::new("^2".to_string()))), a.get("dependencies").unwrap().get("b").unwrap().value());
+        assert_eq!(&NodeValue::Scalar(Value::Boolean(true)), a.get("optional").unwrap().value());
+        assert_eq!(vec!["", ""], a.get("optional").unwrap().leading_trivia().collect::<Vec<_>>());
+        assert_eq!(&NodeValue::Scalar(Value::String(Rc::new("1".to_string()))), doc.get("c").unwrap().value());
+        assert_eq!(vec!["# the end"], doc.trailing.iter().map(|t| t.raw.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let e = Document::parse(b"a:\n    b c\n").unwrap_err();
+        assert_eq!((2, 2, ErrorKind::UnexpectedIndent { expected: 2, found: 4 }), (e.line, e.col, e.kind));
+        let e = Document::parse(b"a:\n  b c d\n").unwrap_err();
+        assert_eq!((2, 6, ErrorKind::UnexpectedToken(TokenKind::String)), (e.line, e.col, e.kind));
+        let e = Document::parse(b"a b\r\n\"c\", :\n").unwrap_err();
+        assert_eq!((2, 5, ErrorKind::ExpectedString), (e.line, e.col, e.kind));
+        let e = Document::parse(b"a\n").unwrap_err();
+        assert_eq!((1, 1, ErrorKind::UnexpectedToken(TokenKind::Eof)), (e.line, e.col, e.kind));
+        let e = Document::parse(b"\n# yarn lockfile v2\n").unwrap_err();
+        assert_eq!((2, ErrorKind::UnsupportedVersion(2)), (e.line, e.kind));
+    }
+}
@@ -0,0 +1,523 @@
+use std::borrow::Cow;
+use std::collections::{hash_map, HashMap};
+use std::fmt;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use serde::de::value::{BorrowedStrDeserializer, MapAccessDeserializer};
+use serde::de::{DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, Visitor};
+use serde::{de, forward_to_deserialize_any, Deserialize, Deserializer};
+
+use crate::parser;
+use crate::parser::{unquote_string, BorrowedValue, ErrorKind, Parser, TokenKind, Value};
+use crate::tokens::Token;
+
+/// Deserialization error.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The input could not be parsed.
+    Parse(parser::Error),
+    /// The input does not match the shape of the target type.
+    Message(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            Self::Message(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::Message(msg) => write!(f, "Deserialization error: {msg}"),
+        }
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+impl From<parser::Error> for Error {
+    fn from(e: parser::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// Deserialize an instance of type `T` from yarn lockfile syntax.
+///
+/// The input is read token by token from the lexer, following the same rules as
+/// [`parser::parse`], without building a [`Value`] first. Strings without escapes are borrowed
+/// from the input, so `T` may hold `&str`. The value of an entry with several keys, e.g.
+/// `"a@^1.0.0", "a@^1.1.0":`, is read again for every key.
+///
+/// # Errors
+/// - [`Error::Parse`]: When parsing failed
+/// - [`Error::Message`]: When the input does not match the shape of `T`
+pub fn from_slice<'de, T: Deserialize<'de>>(input: &'de [u8]) -> Result<T, Error> {
+    let mut parser = Parser::new(input)?;
+    T::deserialize(TokenDeserializer { parser: &mut parser, indent: 0, mapping: true, scalar: false })
+}
+
+/// A value read from the tokens at the position of the parser.
+struct TokenDeserializer<'a, 'de> {
+    parser: &'a mut Parser<'de>,
+    /// The indent of the mapping the value opens, when it is not a scalar.
+    indent: usize,
+    /// Whether the value may be a mapping: the whole input, or a value after `key:`.
+    mapping: bool,
+    /// Whether the value may be a string, number or boolean.
+    scalar: bool,
+}
+
+impl<'de> TokenDeserializer<'_, 'de> {
+    fn unexpected(&self) -> Error {
+        Error::Parse(self.parser.error(ErrorKind::UnexpectedToken(TokenKind::from(&self.parser.cur.token))))
+    }
+
+    /// Whether a mapping starts at the position of the parser, other tokens fail like in
+    /// [`parser::parse`] before the visitor sees them.
+    const fn at_mapping(&self) -> bool {
+        self.mapping && matches!(self.parser.cur.token, Token::NewLine | Token::Indent(_) | Token::Eof | Token::String(_))
+    }
+
+    /// Visit the mapping at the position of the parser, the entries the visitor leaves are skipped.
+    fn visit_mapping<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut access = TokenMapAccess { parser: self.parser, indent: self.indent, keys: vec![], start: None, was_colon: false, done: false };
+        let value = visitor.visit_map(&mut access)?;
+        while access.next_key::<IgnoredAny>()?.is_some() {
+            access.next_value::<IgnoredAny>()?;
+        }
+        Ok(value)
+    }
+}
+
+impl<'de> Deserializer<'de> for TokenDeserializer<'_, 'de> {
+    type Error = Error;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.parser.cur.token {
+            Token::String(s) if self.scalar => {
+                let s = unquote_string(s).map_err(|kind| self.parser.error(kind))?;
+                self.parser.next()?;
+                match s {
+                    Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                    Cow::Owned(s) => visitor.visit_string(s),
+                }
+            }
+            Token::Number(n) if self.scalar => {
+                self.parser.next()?;
+                // the lexer only produces integers
+                if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+                    visitor.visit_i64(n as i64)
+                } else {
+                    visitor.visit_f64(n)
+                }
+            }
+            Token::Bool(b) if self.scalar => {
+                self.parser.next()?;
+                visitor.visit_bool(b)
+            }
+            _ if self.at_mapping() => self.visit_mapping(visitor),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        match self.parser.cur.token {
+            Token::String(s) if self.scalar => {
+                let s = unquote_string(s).map_err(|kind| self.parser.error(kind))?;
+                self.parser.next()?;
+                visitor.visit_enum(s.into_owned().into_deserializer())
+            }
+            Token::String(_) | Token::Number(_) | Token::Bool(_) if self.scalar => {
+                let unexpected = match self.parser.cur.token {
+                    Token::Number(n) => de::Unexpected::Float(n),
+                    Token::Bool(b) => de::Unexpected::Bool(b),
+                    _ => de::Unexpected::Other("a token"),
+                };
+                Err(de::Error::invalid_type(unexpected, &"a string or an object with a single key"))
+            }
+            _ if self.at_mapping() => {
+                let mut access = TokenMapAccess { parser: self.parser, indent: self.indent, keys: vec![], start: None, was_colon: false, done: false };
+                let value = visitor.visit_enum(MapAccessDeserializer::new(&mut access))?;
+                if access.next_key::<IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_type(de::Unexpected::Map, &"a string or an object with a single key"));
+                }
+                Ok(value)
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// The entries of a mapping read from the tokens, following `Parser::parse`.
+struct TokenMapAccess<'a, 'de> {
+    parser: &'a mut Parser<'de>,
+    indent: usize,
+    /// The keys of the current entry not read yet, last key first.
+    keys: Vec<Cow<'de, str>>,
+    /// The parser at the value of the current entry, when it has more keys.
+    start: Option<Parser<'de>>,
+    was_colon: bool,
+    done: bool,
+}
+
+impl<'de> TokenMapAccess<'_, 'de> {
+    fn key(&self, s: &'de [u8]) -> Result<Cow<'de, str>, Error> {
+        let key = unquote_string(s).map_err(|kind| self.parser.error(kind))?;
+        if key.is_empty() {
+            return Err(Error::Parse(self.parser.error(ErrorKind::ExpectedKey)));
+        }
+        Ok(key)
+    }
+}
+
+impl<'de> MapAccess<'de> for TokenMapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        if let Some(key) = self.keys.pop() {
+            if let Some(start) = &self.start {
+                self.parser.clone_from(start);
+            }
+            return deserialize_key(seed, key).map(Some);
+        }
+        self.start = None;
+        while !self.done {
+            match self.parser.cur.token {
+                Token::NewLine => {
+                    let next_token = self.parser.next()?;
+                    if self.indent == 0 {
+                        // if we have indentation 0, then the next token doesn't matter
+                        continue;
+                    }
+                    match next_token.token {
+                        Token::Indent(n) if n == self.indent => {
+                            self.parser.next()?;
+                        }
+                        Token::Indent(_) => {}
+                        _ => self.done = true,
+                    }
+                }
+                Token::Indent(n) if n == self.indent => {
+                    self.parser.next()?;
+                }
+                Token::Indent(n) => self.done = self.parser.ends_mapping(n, self.indent)?,
+                Token::Eof => self.done = true,
+                Token::String(s) => {
+                    let mut keys = vec![self.key(s)?];
+                    self.parser.next()?;
+                    // support multiple keys
+                    while self.parser.cur.token == Token::Comma {
+                        self.parser.next()?;
+                        let Token::String(s) = self.parser.cur.token else {
+                            return Err(Error::Parse(self.parser.error(ErrorKind::ExpectedString)));
+                        };
+                        keys.push(self.key(s)?);
+                        self.parser.next()?;
+                    }
+                    self.was_colon = self.parser.cur.token == Token::Colon;
+                    if self.was_colon {
+                        self.parser.next()?;
+                    }
+                    keys.reverse();
+                    let key = keys.pop().unwrap_or_default();
+                    if !keys.is_empty() {
+                        self.start = Some(self.parser.clone());
+                    }
+                    self.keys = keys;
+                    return deserialize_key(seed, key).map(Some);
+                }
+                token => return Err(Error::Parse(self.parser.error(ErrorKind::UnexpectedToken(TokenKind::from(&token))))),
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let nested = !matches!(self.parser.cur.token, Token::String(_) | Token::Number(_) | Token::Bool(_));
+        let value = seed.deserialize(TokenDeserializer { parser: self.parser, indent: self.indent + 2, mapping: self.was_colon, scalar: true })?;
+        if nested && self.indent != 0 && !matches!(self.parser.cur.token, Token::Indent(_)) {
+            self.done = true;
+        }
+        Ok(value)
+    }
+}
+
+fn deserialize_key<'de, K: DeserializeSeed<'de>>(seed: K, key: Cow<'de, str>) -> Result<K::Value, Error> {
+    match key {
+        Cow::Borrowed(key) => seed.deserialize(BorrowedStrDeserializer::new(key)),
+        Cow::Owned(key) => seed.deserialize(key.into_deserializer()),
+    }
+}
+
+impl<'de> Deserializer<'de> for BorrowedValue<'de> {
+    type Error = Error;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            Self::String(Cow::Owned(s)) => visitor.visit_string(s),
+            // the lexer only produces integers
+            Self::Number(n) if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 => visitor.visit_i64(n as i64),
+            Self::Number(n) => visitor.visit_f64(n),
+            Self::Boolean(b) => visitor.visit_bool(b),
+            Self::Object(map) => visitor.visit_map(ObjectAccess { iter: map.into_iter(), value: None }),
+            Self::Null => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::Null => visitor.visit_none(),
+            v => visitor.visit_some(v),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Self::String(s) => visitor.visit_enum(s.into_owned().into_deserializer()),
+            Self::Object(map) if map.len() == 1 => visitor.visit_enum(MapAccessDeserializer::new(ObjectAccess { iter: map.into_iter(), value: None })),
+            v => Err(de::Error::invalid_type(v.unexpected(), &"a string or an object with a single key")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for BorrowedValue<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl BorrowedValue<'_> {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Self::String(s) => de::Unexpected::Str(s),
+            Self::Number(n) => de::Unexpected::Float(*n),
+            Self::Boolean(b) => de::Unexpected::Bool(*b),
+            Self::Object(_) => de::Unexpected::Map,
+            Self::Null => de::Unexpected::Unit,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+#[allow(clippy::cast_precision_loss)]
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a string, number, boolean, map or null")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Number(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(Rc::new(v.to_string())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(Rc::new(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = HashMap::new();
+        while let Some((k, v)) = access.next_entry()? {
+            map.insert(k, v);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+struct ObjectAccess<'de> {
+    iter: hash_map::IntoIter<Cow<'de, str>, BorrowedValue<'de>>,
+    value: Option<BorrowedValue<'de>>,
+}
+
+impl<'de> MapAccess<'de> for ObjectAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((k, v)) => {
+                self.value = Some(v);
+                seed.deserialize(BorrowedValue::String(k)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self.value.take().ok_or_else(|| Error::Message("value is missing".to_string()))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry<'a> {
+        version: String,
+        resolved: Option<&'a str>,
+        integrity: Option<String>,
+        #[serde(default)]
+        dependencies: BTreeMap<String, String>,
+        #[serde(default)]
+        optional_dependencies: BTreeMap<String, String>,
+    }
+
+    #[test]
+    fn deserialize_lockfile() {
+        let lockfile: HashMap<String, Entry> = from_slice(include_bytes!("test.lock.2")).unwrap();
+        let entry = &lockfile["@babel/code-frame@^7.18.6"];
+        assert_eq!("7.21.4", entry.version);
+        assert_eq!(Some("https://registry.yarnpkg.com/@babel/code-frame/-/code-frame-7.21.4.tgz#d0fa9e4413aca81f2b23b9442797bda1826edb39"), entry.resolved);
+        assert_eq!(Some("^7.18.6"), entry.dependencies.get("@babel/highlight").map(String::as_str));
+        assert_eq!(&lockfile["@babel/code-frame@^7.0.0"], entry);
+        assert_eq!(Some("~2.3.2"), lockfile["chokidar@^3.5.1"].optional_dependencies.get("fsevents").map(String::as_str));
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Strict,
+        Loose,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        enabled: bool,
+        retries: u32,
+        mode: Mode,
+        name: String,
+        missing: Option<String>,
+        nested: HashMap<String, i64>,
+    }
+
+    #[test]
+    fn deserialize_scalars() {
+        let input = b"enabled true\nretries 3\nmode strict\nname \"a\\\"b\"\nnested:\n  x 1\n  y 2\n";
+        let config: Config = from_slice(input).unwrap();
+        assert_eq!(Config {
+            enabled: true,
+            retries: 3,
+            mode: Mode::Strict,
+            name: "a\"b".to_string(),
+            missing: None,
+            nested: HashMap::from([("x".to_string(), 1), ("y".to_string(), 2)]),
+        }, config);
+        assert_ne!(Mode::Loose, config.mode);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Source {
+        Git { url: String },
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Partial {
+        b: String,
+        source: Source,
+    }
+
+    #[test]
+    fn deserialize_tokens() {
+        let input: &[u8] = b"a:\n  b \"1\"\n  skipped:\n    c:\n      d e\n  source:\n    Git:\n      url \"x\"\nf, g:\n  b \"2\"\n  source:\n    Git:\n      url \"y\"\n";
+        let map: BTreeMap<&str, Partial> = from_slice(input).unwrap();
+        assert_eq!(Partial { b: "1".to_string(), source: Source::Git { url: "x".to_string() } }, map["a"]);
+        assert_eq!(map["f"], map["g"]);
+        assert_eq!(3, map.len());
+        assert_eq!(crate::parser::parse(input).unwrap(), from_slice::<Value>(input).unwrap());
+        let lockfile: BTreeMap<&str, Value> = from_slice(include_bytes!("test.lock.1")).unwrap();
+        let BorrowedValue::Object(map) = crate::parser::parse_borrowed(include_bytes!("test.lock.1")).unwrap() else { panic!() };
+        assert_eq!(map.len(), lockfile.len());
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        assert!(matches!(from_slice::<Config>(b"enabled \"yes\"\n"), Err(Error::Message(_))));
+        assert!(matches!(from_slice::<Config>(b"a:\n   b c\n"), Err(Error::Parse(_))));
+        let err = from_slice::<BTreeMap<String, BTreeMap<String, String>>>(b"a:\n  b c\n    d e\n").unwrap_err();
+        assert!(matches!(err, Error::Parse(parser::Error { kind: ErrorKind::UnexpectedIndent { expected: 2, found: 4 }, .. })));
+        assert!(matches!(from_slice::<BTreeMap<String, String>>(b"a b\nc:: d\n"), Err(Error::Parse(parser::Error { kind: ErrorKind::UnexpectedToken(TokenKind::Colon), .. }))));
+        assert!(matches!(from_slice::<BTreeMap<String, Source>>(b"a:\n  Git:\n    url x\n  Svn:\n    url y\n"), Err(Error::Message(_))));
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use crate::descriptor::Descriptor;
+use crate::lockfile::{Entry, Lockfile};
+use crate::semver::{Range, Version};
+
+/// How [`plan`] picks the version a descriptor is collapsed onto, like `yarn-deduplicate`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// The highest version that satisfies the range.
+    #[default]
+    Highest,
+    /// The version that satisfies the most ranges of the package, then the highest one, to end
+    /// up with fewer versions.
+    Fewer,
+}
+
+/// A descriptor that can be collapsed onto another version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The descriptor, e.g. `lodash@^4.17.0`.
+    pub descriptor: String,
+    /// The version the descriptor resolves to now.
+    pub from: String,
+    /// The version the descriptor can resolve to instead.
+    pub to: String,
+}
+
+/// The distinct resolved versions of every package name, ordered by semver.
+///
+/// Versions that are not valid semver, like some git dependencies, come last. An `npm:` alias
+/// counts as the package it points to, see [`Descriptor::effective_name`].
+#[must_use]
+pub fn versions(lockfile: &Lockfile) -> BTreeMap<String, Vec<String>> {
+    let mut versions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (descriptor, entry) in lockfile.iter() {
+        let list = versions.entry(Descriptor::parse(descriptor).effective_name()).or_default();
+        if !list.contains(&entry.version) {
+            list.push(entry.version.clone());
+        }
+    }
+    for list in versions.values_mut() {
+        list.sort_by_cached_key(|v| Version::parse(v).map_err(|_| v.clone()));
+    }
+    versions
+}
+
+/// The package names resolved to more than one version, with their versions, see [`versions`].
+#[must_use]
+pub fn duplicates(lockfile: &Lockfile) -> BTreeMap<String, Vec<String>> {
+    let mut versions = versions(lockfile);
+    versions.retain(|_, list| list.len() > 1);
+    versions
+}
+
+/// Find the descriptors that can be collapsed onto another version already in the lockfile.
+///
+/// The new version satisfies the range of the descriptor. Only descriptors with a semver range,
+/// see [`Descriptor::semver_range`], are considered, as candidates and as targets. An `npm:` alias
+/// is only collapsed onto versions of the package it points to.
+#[must_use]
+pub fn plan(lockfile: &Lockfile, strategy: Strategy) -> Vec<Change> {
+    plan_entries(lockfile, strategy).into_iter().map(|(change, _)| change).collect()
+}
+
+/// Collapse the descriptors found by [`plan`] onto their new version, and return the changes.
+///
+/// Each changed descriptor gets a copy of an entry of the new version, so the entries of versions
+/// no descriptor resolves to anymore are removed.
+pub fn dedupe(lockfile: &mut Lockfile, strategy: Strategy) -> Vec<Change> {
+    let changes = plan_entries(lockfile, strategy);
+    changes
+        .into_iter()
+        .map(|(change, entry)| {
+            lockfile.entries.insert(change.descriptor.clone(), entry);
+            change
+        })
+        .collect()
+}
+
+fn plan_entries(lockfile: &Lockfile, strategy: Strategy) -> Vec<(Change, Entry)> {
+    struct Candidate<'l> {
+        descriptor: &'l str,
+        range: Range,
+        version: Version,
+        entry: &'l Entry,
+    }
+    let mut packages: BTreeMap<String, Vec<Candidate>> = BTreeMap::new();
+    for (descriptor, entry) in lockfile.iter() {
+        let parsed = Descriptor::parse(descriptor);
+        if let (Some(range), Ok(version)) = (parsed.semver_range(), Version::parse(&entry.version)) {
+            packages.entry(parsed.effective_name()).or_default().push(Candidate { descriptor, range, version, entry });
+        }
+    }
+    let mut changes = vec![];
+    for candidates in packages.values() {
+        // the first entry of every version, highest version first
+        let mut versions: Vec<(&Version, &Entry)> = vec![];
+        for candidate in candidates {
+            if !versions.iter().any(|(v, _)| *v == &candidate.version) {
+                versions.push((&candidate.version, candidate.entry));
+            }
+        }
+        versions.sort_by(|a, b| b.0.cmp(a.0));
+        if versions.len() < 2 {
+            continue;
+        }
+        if strategy == Strategy::Fewer {
+            let satisfied = |v: &Version| candidates.iter().filter(|c| c.range.satisfies(v)).count();
+            // a stable sort keeps the highest version first on a tie
+            versions.sort_by_cached_key(|(v, _)| std::cmp::Reverse(satisfied(v)));
+        }
+        for candidate in candidates {
+            let Some((version, entry)) = versions.iter().find(|(v, _)| candidate.range.satisfies(v)) else {
+                continue;
+            };
+            if *version != &candidate.version {
+                let change = Change { descriptor: candidate.descriptor.to_string(), from: candidate.entry.version.clone(), to: entry.version.clone() };
+                changes.push((change, (*entry).clone()));
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = r#"# yarn lockfile v1
+
+
+a@^1.0.0:
+  version "1.0.0"
+
+a@^1.1.0:
+  version "1.2.0"
+
+"a@1 || 2":
+  version "2.0.0"
+
+"a@github:user/a":
+  version "3.0.0"
+
+b@^1.0.0, b@^1.0.1:
+  version "1.0.1"
+"#;
+
+    fn change(descriptor: &str, from: &str, to: &str) -> Change {
+        Change { descriptor: descriptor.to_string(), from: from.to_string(), to: to.to_string() }
+    }
+
+    #[test]
+    fn find_duplicates() {
+        let lockfile = Lockfile::parse(LOCKFILE.as_bytes()).unwrap();
+        let versions = versions(&lockfile);
+        assert_eq!(Some(&vec!["1.0.1".to_string()]), versions.get("b"));
+        let expected: Vec<String> = ["1.0.0", "1.2.0", "2.0.0", "3.0.0"].map(str::to_string).to_vec();
+        assert_eq!(BTreeMap::from([("a".to_string(), expected)]), duplicates(&lockfile));
+    }
+
+    #[test]
+    fn dedupe_strategies() {
+        let lockfile = Lockfile::parse(LOCKFILE.as_bytes()).unwrap();
+        assert_eq!(vec![change("a@^1.0.0", "1.0.0", "1.2.0")], plan(&lockfile, Strategy::Highest));
+        assert_eq!(vec![change("a@1 || 2", "2.0.0", "1.2.0"), change("a@^1.0.0", "1.0.0", "1.2.0")], plan(&lockfile, Strategy::Fewer));
+
+        let mut deduped = lockfile.clone();
+        assert_eq!(plan(&lockfile, Strategy::Fewer), dedupe(&mut deduped, Strategy::Fewer));
+        assert_eq!(vec!["1.2.0", "3.0.0"], duplicates(&deduped)["a"]);
+        assert!(plan(&deduped, Strategy::Fewer).is_empty());
+        assert!(deduped.to_string().contains("\"a@1 || 2\", a@^1.0.0, a@^1.1.0:\n  version \"1.2.0\"\n"));
+    }
+
+    #[test]
+    fn dedupe_aliases() {
+        let lockfile = Lockfile::parse(b"react@^17.0.0:\n  version \"17.0.2\"\n\n\"react@npm:@preact/compat@^17.0.0\":\n  version \"17.1.2\"\n").unwrap();
+        assert!(plan(&lockfile, Strategy::Highest).is_empty());
+        assert!(duplicates(&lockfile).is_empty());
+        assert_eq!(Some(&vec!["17.1.2".to_string()]), versions(&lockfile).get("@preact/compat"));
+
+        let mut lockfile = lockfile;
+        lockfile.entries.insert("@preact/compat@^17.1.0".to_string(), lockfile.entries["react@npm:@preact/compat@^17.0.0"].clone());
+        lockfile.entries.get_mut("@preact/compat@^17.1.0").unwrap().version = "17.1.5".to_string();
+        assert_eq!(vec![change("react@npm:@preact/compat@^17.0.0", "17.1.2", "17.1.5")], plan(&lockfile, Strategy::Highest));
+    }
+}
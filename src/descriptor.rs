@@ -0,0 +1,306 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use crate::semver::Range;
+
+/// The hosts a URL with a `user/repo` path is a git repository on.
+pub(crate) const GIT_HOSTS: [&str; 4] = ["github.com", "gitlab.com", "bitbucket.com", "bitbucket.org"];
+
+/// A package pattern, the key of a lockfile entry, e.g. `@babel/core@^7.12.3`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Descriptor {
+    /// The package name, including the scope, e.g. `@babel/core`.
+    pub name: String,
+    /// The range, `latest` when the pattern has none.
+    pub range: String,
+    /// Whether the pattern has a range.
+    pub has_version: bool,
+}
+
+/// The protocol of a descriptor range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RangeKind {
+    /// A semver range, e.g. `^1.2.0`.
+    Semver,
+    /// A dist-tag, e.g. `latest`, any range that is not a valid semver range.
+    Tag,
+    /// A git repository, e.g. `git+ssh://git@github.com/user/repo.git#sha` or `user/repo`.
+    Git,
+    /// A tarball URL, e.g. `https://example.com/pkg.tgz`.
+    Tarball,
+    /// A local path, e.g. `file:../pkg` or `./pkg`.
+    File,
+    /// A `link:` path.
+    Link,
+    /// An `npm:` range, either an alias like `npm:other@^1.0.0` or a Berry range like `npm:^1.0.0`.
+    Npm,
+    /// A `workspace:` range.
+    Workspace,
+    /// A Berry `patch:` range.
+    Patch,
+    /// A Berry `portal:` path.
+    Portal,
+    /// A Berry `exec:` script.
+    Exec,
+}
+
+impl Descriptor {
+    /// Split a pattern into name and range.
+    ///
+    /// Translated from [https://github.com/yarnpkg/yarn/blob/master/src/util/normalize-pattern.js](https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/src/util/normalize-pattern.js#L6)
+    /// Keep code-style consistent with the original code.
+    #[must_use]
+    pub fn parse(pattern: &str) -> Self {
+        let mut has_version = false;
+        let mut range = "latest".to_string();
+        let mut name = pattern;
+
+        // if we're a scope then remove the @ and add it back later
+        let is_scoped = name.starts_with('@');
+        if is_scoped {
+            name = &name[1..];
+        }
+
+        // take first part as the name
+        if let Some((first, rest)) = name.split_once('@') {
+            name = first;
+            if rest.is_empty() {
+                range = "*".to_string();
+            } else {
+                range = rest.to_string();
+                has_version = true;
+            }
+        }
+
+        // add back @ scope suffix
+        let name = if is_scoped { format!("@{name}") } else { name.to_string() };
+
+        Self { name, range, has_version }
+    }
+
+    /// The scope of the package name, e.g. `@babel` for `@babel/core`.
+    #[must_use]
+    pub fn scope(&self) -> Option<&str> {
+        self.name.strip_prefix('@').and_then(|_| self.name.split_once('/')).map(|(scope, _)| scope)
+    }
+
+    /// The package name without the scope, e.g. `core` for `@babel/core`.
+    #[must_use]
+    pub fn bare_name(&self) -> &str {
+        match self.scope() {
+            Some(scope) => &self.name[scope.len() + 1..],
+            None => &self.name,
+        }
+    }
+
+    /// The protocol of the range.
+    #[must_use]
+    pub fn kind(&self) -> RangeKind {
+        range_kind(&self.range)
+    }
+
+    /// The descriptor an `npm:` alias points to, e.g. `bar@^1.0.0` for `foo@npm:bar@^1.0.0`.
+    ///
+    /// Returns `None` when the range is not an alias, including Berry ranges like `npm:^1.0.0`.
+    #[must_use]
+    pub fn alias(&self) -> Option<Self> {
+        let target = self.range.strip_prefix("npm:")?;
+        (target.starts_with('@') || target.contains('@')).then(|| Self::parse(target))
+    }
+
+    /// The name of the package the descriptor resolves to, following `npm:` aliases.
+    #[must_use]
+    pub fn effective_name(&self) -> String {
+        self.alias().map_or_else(|| self.name.clone(), |alias| alias.name)
+    }
+
+    /// The range without the `npm:` protocol, following `npm:` aliases.
+    #[must_use]
+    pub fn effective_range(&self) -> String {
+        match self.alias() {
+            Some(alias) => alias.range,
+            None => self.range.strip_prefix("npm:").unwrap_or(&self.range).to_string(),
+        }
+    }
+
+    /// The semver range, following `npm:` aliases.
+    ///
+    /// Returns `None` for tags, git, tarballs, paths and the other protocols.
+    #[must_use]
+    pub fn semver_range(&self) -> Option<Range> {
+        match self.kind() {
+            RangeKind::Semver => Range::parse(&self.range).ok(),
+            RangeKind::Npm => Range::parse(&self.effective_range()).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Descriptor {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for Descriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.has_version {
+            write!(f, "{}@{}", self.name, self.range)
+        } else {
+            f.write_str(&self.name)
+        }
+    }
+}
+
+fn range_kind(range: &str) -> RangeKind {
+    const PROTOCOLS: [(&str, RangeKind); 7] = [
+        ("npm:", RangeKind::Npm),
+        ("workspace:", RangeKind::Workspace),
+        ("file:", RangeKind::File),
+        ("link:", RangeKind::Link),
+        ("patch:", RangeKind::Patch),
+        ("portal:", RangeKind::Portal),
+        ("exec:", RangeKind::Exec),
+    ];
+    if let Some((_, kind)) = PROTOCOLS.iter().find(|(p, _)| range.starts_with(p)) {
+        return *kind;
+    }
+    if ["./", "../", "/", "~/"].iter().any(|p| range.starts_with(p)) {
+        return RangeKind::File;
+    }
+    if is_git(range) {
+        return RangeKind::Git;
+    }
+    if range.starts_with("http://") || range.starts_with("https://") {
+        return RangeKind::Tarball;
+    }
+    if Range::parse(range).is_ok() {
+        RangeKind::Semver
+    } else {
+        RangeKind::Tag
+    }
+}
+
+/// Port of `GitResolver.isVersion` and the hosted git resolvers' `isVersion`.
+fn is_git(range: &str) -> bool {
+    let before_hash = range.split_once('#').map_or(range, |(r, _)| r);
+    if ["git:", "ssh:", "github:", "gitlab:", "bitbucket:", "gist:"].iter().any(|p| range.starts_with(p))
+        || range.strip_prefix("git+").is_some_and(|r| r.contains(':'))
+    {
+        return true;
+    }
+    if let Some(rest) = range.strip_prefix("https://").or_else(|| range.strip_prefix("http://")) {
+        if before_hash.strip_suffix(".git").is_some() {
+            return true;
+        }
+        // only a repository on a git host, not a file in it like `user/repo/archive/v1.0.0.tar.gz`
+        let rest = rest.split_once('#').map_or(rest, |(r, _)| r);
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        return GIT_HOSTS.contains(&host) && path.split('/').filter(|p| !p.is_empty()).count() == 2;
+    }
+    is_github_shorthand(before_hash)
+}
+
+/// Match `/^[^:@%/\s.-][^:@%/\s]*[/][^:@\s/%]+(?:#.*)?$/`, the pattern stripped of its `#` part.
+fn is_github_shorthand(pattern: &str) -> bool {
+    let Some((user, repo)) = pattern.split_once('/') else {
+        return false;
+    };
+    let invalid = |c: char| matches!(c, ':' | '@' | '%' | '/') || c.is_whitespace();
+    !user.is_empty()
+        && !user.starts_with(['.', '-'])
+        && !user.contains(invalid)
+        && !repo.is_empty()
+        && !repo.contains(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semver::Version;
+
+    fn d(pattern: &str) -> (String, String, bool) {
+        let d = Descriptor::parse(pattern);
+        (d.name, d.range, d.has_version)
+    }
+
+    #[test]
+    fn normalize_pattern() {
+        // https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/__tests__/util/normalize-pattern.js
+        let s = |s: &str| s.to_string();
+        assert_eq!((s("lodash"), s("^4.17.21"), true), d("lodash@^4.17.21"));
+        assert_eq!((s("@colors/colors"), s("1.5.0"), true), d("@colors/colors@1.5.0"));
+        assert_eq!((s("@scope/name"), s("latest"), false), d("@scope/name"));
+        assert_eq!((s("@scope/name"), s("*"), false), d("@scope/name@"));
+        assert_eq!((s("name"), s("latest"), false), d("name"));
+        assert_eq!((s("foo"), s("npm:@scope/bar@^1"), true), d("foo@npm:@scope/bar@^1"));
+        assert_eq!((s("@"), s("latest"), false), d("@"));
+        assert_eq!("@scope/name@^1", Descriptor::parse("@scope/name@^1").to_string());
+        assert_eq!("name", Descriptor::parse("name").to_string());
+    }
+
+    #[test]
+    fn scope_and_alias() {
+        let desc = Descriptor::parse("@babel/core@^7.0.0");
+        assert_eq!((Some("@babel"), "core"), (desc.scope(), desc.bare_name()));
+        assert_eq!((None, "core"), (Descriptor::parse("core@1").scope(), Descriptor::parse("core@1").bare_name()));
+
+        let desc = Descriptor::parse("string-width-cjs@npm:string-width@^4.2.0");
+        assert_eq!(Some(Descriptor::parse("string-width@^4.2.0")), desc.alias());
+        assert_eq!(("string-width".to_string(), "^4.2.0".to_string()), (desc.effective_name(), desc.effective_range()));
+        let desc = Descriptor::parse("picocolors@npm:^1.0.0");
+        assert_eq!(None, desc.alias());
+        assert_eq!(("picocolors".to_string(), "^1.0.0".to_string()), (desc.effective_name(), desc.effective_range()));
+    }
+
+    #[test]
+    fn semver_ranges() {
+        let satisfies = |pattern: &str, version: &str| Descriptor::parse(pattern).semver_range().unwrap().satisfies(&Version::parse(version).unwrap());
+        assert!(satisfies("foo@^2.0.0", "2.1.0"));
+        assert!(!satisfies("foo@^2.0.0", "1.9.0"));
+        assert!(satisfies("foo@npm:bar@~1.2.0", "1.2.9"));
+        assert!(!satisfies("foo@npm:^1.2.0", "2.0.0"));
+        for pattern in ["foo", "foo@next", "foo@npm:bar@latest", "foo@github:user/repo", "foo@file:../foo"] {
+            assert_eq!(None, Descriptor::parse(pattern).semver_range(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn range_kinds() {
+        for (pattern, kind) in [
+            ("lodash@^4.17.21", RangeKind::Semver),
+            ("lodash@4.x", RangeKind::Semver),
+            ("lodash@x", RangeKind::Semver),
+            ("lodash@v1.2.3", RangeKind::Semver),
+            ("lodash@>= 1.0.0 < 2", RangeKind::Semver),
+            ("lodash@*", RangeKind::Semver),
+            ("lodash", RangeKind::Tag),
+            ("lodash@next", RangeKind::Tag),
+            ("lodash@vnext", RangeKind::Tag),
+            ("lodash@beta-1", RangeKind::Tag),
+            ("lodash@1.2.3-beta", RangeKind::Semver),
+            ("react-data-grid@https://github.com/adazzle/react-data-grid.git#sha", RangeKind::Git),
+            ("react-data-grid@https://github.com/adazzle/react-data-grid#sha", RangeKind::Git),
+            ("a@git+ssh://git@github.com/user/repo.git#v1.0.0", RangeKind::Git),
+            ("a@git://github.com/user/repo", RangeKind::Git),
+            ("a@github:user/repo", RangeKind::Git),
+            ("a@user/repo#semver:^1.0.0", RangeKind::Git),
+            ("a@https://github.com/user/repo/archive/v1.0.0.tar.gz", RangeKind::Tarball),
+            ("a@https://registry.yarnpkg.com/a/-/a-1.0.0.tgz", RangeKind::Tarball),
+            ("a@file:../a", RangeKind::File),
+            ("a@./a", RangeKind::File),
+            ("a@~/a", RangeKind::File),
+            ("a@link:../a", RangeKind::Link),
+            ("foo@npm:bar@^1", RangeKind::Npm),
+            ("a@workspace:packages/a", RangeKind::Workspace),
+            ("typescript@patch:typescript@npm%3A^5.0.0#optional!builtin<compat/typescript>", RangeKind::Patch),
+            ("a@portal:../a", RangeKind::Portal),
+            ("a@exec:./gen.js", RangeKind::Exec),
+        ] {
+            assert_eq!(kind, Descriptor::parse(pattern).kind(), "{pattern}");
+        }
+    }
+}
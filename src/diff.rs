@@ -0,0 +1,292 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::descriptor::Descriptor;
+use crate::lockfile::{Entry, Lockfile};
+use crate::semver::Version;
+
+/// The semantic difference between two lockfiles, see [`diff`].
+///
+/// Every list is ordered by package name, then version or descriptor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    /// The package versions only in the new lockfile, that are not an upgrade or a downgrade.
+    pub added: Vec<Package>,
+    /// The package versions only in the old lockfile, that are not an upgrade or a downgrade.
+    pub removed: Vec<Package>,
+    /// The package versions replaced by a higher version.
+    pub upgraded: Vec<VersionChange>,
+    /// The package versions replaced by a lower version.
+    pub downgraded: Vec<VersionChange>,
+    /// The descriptors in both lockfiles that resolve to another version.
+    pub moved: Vec<Moved>,
+    /// The package versions in both lockfiles fetched from another source: a descriptor in both
+    /// whose `resolved` or `integrity` changed, or a `resolved` and `integrity` pair the old
+    /// lockfile does not have for that version. The same version fetched from another source or
+    /// with another checksum deserves a review.
+    pub changed: Vec<SourceChange>,
+}
+
+/// A package version.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+}
+
+/// A package version replaced by another version.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VersionChange {
+    pub name: String,
+    /// The version in the old lockfile.
+    pub from: String,
+    /// The version in the new lockfile.
+    pub to: String,
+}
+
+/// The semver part a [`VersionChange`] changed, the left-most one that differs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Bump {
+    Major,
+    Minor,
+    Patch,
+    /// Only the prerelease identifiers changed, e.g. `1.0.0-beta.1` to `1.0.0`.
+    Prerelease,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Prerelease => "prerelease",
+        })
+    }
+}
+
+/// A descriptor that resolves to another version.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Moved {
+    /// The descriptor, e.g. `lodash@^4.17.0`.
+    pub descriptor: String,
+    /// The version in the old lockfile.
+    pub from: String,
+    /// The version in the new lockfile.
+    pub to: String,
+}
+
+/// A package version whose source changed.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceChange {
+    pub name: String,
+    pub version: String,
+    /// The `resolved` field in the old and the new lockfile, when it changed.
+    pub resolved: Option<(Option<String>, Option<String>)>,
+    /// The `integrity` field in the old and the new lockfile, when it changed.
+    pub integrity: Option<(Option<String>, Option<String>)>,
+}
+
+impl VersionChange {
+    /// The semver part that changed, `None` when either version is not semver or they are equal
+    /// but for build metadata.
+    #[must_use]
+    pub fn bump(&self) -> Option<Bump> {
+        let (Ok(from), Ok(to)) = (Version::parse(&self.from), Version::parse(&self.to)) else {
+            return None;
+        };
+        if from.major != to.major {
+            Some(Bump::Major)
+        } else if from.minor != to.minor {
+            Some(Bump::Minor)
+        } else if from.patch != to.patch {
+            Some(Bump::Patch)
+        } else {
+            (from.pre != to.pre).then_some(Bump::Prerelease)
+        }
+    }
+}
+
+impl Diff {
+    /// Returns `true` if no difference was found.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.upgraded.is_empty() && self.downgraded.is_empty() && self.moved.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two lockfiles by package.
+///
+/// For every package name, the versions only in `old` are paired with the versions only in `new`
+/// into upgrades and downgrades: first along the descriptors that moved from one to the other,
+/// then in version order. The versions left without a pair are added or removed.
+#[must_use]
+pub fn diff(old: &Lockfile, new: &Lockfile) -> Diff {
+    let mut result = Diff::default();
+    for (descriptor, entry) in old.iter() {
+        let Some(new_entry) = new.get(descriptor) else {
+            continue;
+        };
+        if new_entry.version != entry.version {
+            result.moved.push(Moved { descriptor: descriptor.to_string(), from: entry.version.clone(), to: new_entry.version.clone() });
+        } else if let Some(change) = source_change(&Descriptor::parse(descriptor).name, entry, new_entry) {
+            result.changed.push(change);
+        }
+    }
+    let (old_packages, new_packages) = (packages(old), packages(new));
+    let names: BTreeSet<&String> = old_packages.keys().chain(new_packages.keys()).collect();
+    for name in names {
+        let empty = BTreeMap::new();
+        let old_versions = old_packages.get(name).unwrap_or(&empty);
+        let new_versions = new_packages.get(name).unwrap_or(&empty);
+        let mut removed: Vec<&String> = old_versions.keys().filter(|v| !new_versions.contains_key(*v)).collect();
+        let mut added: Vec<&String> = new_versions.keys().filter(|v| !old_versions.contains_key(*v)).collect();
+        let mut pairs = vec![];
+        for moved in result.moved.iter().filter(|m| Descriptor::parse(&m.descriptor).name == *name) {
+            if let (Some(i), Some(j)) = (removed.iter().position(|v| **v == moved.from), added.iter().position(|v| **v == moved.to)) {
+                pairs.push((removed.remove(i), added.remove(j)));
+            }
+        }
+        removed.sort_by(|a, b| compare(a, b));
+        added.sort_by(|a, b| compare(a, b));
+        let paired = removed.len().min(added.len());
+        pairs.extend(removed.drain(..paired).zip(added.drain(..paired)));
+        for (from, to) in pairs {
+            let change = VersionChange { name: name.clone(), from: from.clone(), to: to.clone() };
+            if compare(from, to) == Ordering::Greater {
+                result.downgraded.push(change);
+            } else {
+                result.upgraded.push(change);
+            }
+        }
+        result.removed.extend(removed.into_iter().map(|version| Package { name: name.clone(), version: version.clone() }));
+        result.added.extend(added.into_iter().map(|version| Package { name: name.clone(), version: version.clone() }));
+        for (version, old_entries) in old_versions {
+            let Some(new_entries) = new_versions.get(version) else {
+                continue;
+            };
+            for new_entry in new_entries.iter().filter(|new_entry| !old_entries.iter().any(|old_entry| same_source(old_entry, new_entry))) {
+                // compare with the old source closest to the new one
+                let old_entry = old_entries.iter().find(|e| e.resolved == new_entry.resolved || e.integrity == new_entry.integrity).unwrap_or(&old_entries[0]);
+                result.changed.extend(source_change(name, old_entry, new_entry));
+            }
+        }
+    }
+    result.upgraded.sort();
+    result.downgraded.sort();
+    result.changed.sort();
+    result.changed.dedup();
+    result
+}
+
+/// The entries with a distinct `resolved` and `integrity` of every version of every package name.
+fn packages(lockfile: &Lockfile) -> BTreeMap<String, BTreeMap<String, Vec<&Entry>>> {
+    let mut packages: BTreeMap<String, BTreeMap<String, Vec<&Entry>>> = BTreeMap::new();
+    for (descriptor, entry) in lockfile.iter() {
+        let sources = packages.entry(Descriptor::parse(descriptor).name).or_default().entry(entry.version.clone()).or_default();
+        if !sources.iter().any(|e| same_source(e, entry)) {
+            sources.push(entry);
+        }
+    }
+    packages
+}
+
+fn same_source(a: &Entry, b: &Entry) -> bool {
+    a.resolved == b.resolved && a.integrity == b.integrity
+}
+
+/// The `resolved` and `integrity` changes between two entries of the same version.
+fn source_change(name: &str, old: &Entry, new: &Entry) -> Option<SourceChange> {
+    let changed = |field: fn(&Entry) -> &Option<String>| (field(old) != field(new)).then(|| (field(old).clone(), field(new).clone()));
+    let resolved = changed(|e| &e.resolved);
+    let integrity = changed(|e| &e.integrity);
+    (resolved.is_some() || integrity.is_some()).then(|| SourceChange { name: name.to_string(), version: new.version.clone(), resolved, integrity })
+}
+
+/// Compare versions by semver, falling back to comparing the text when either is not semver.
+fn compare(a: &str, b: &str) -> Ordering {
+    match (Version::parse(a), Version::parse(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lockfile(entries: &[(&str, &str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::default();
+        for (descriptor, version, integrity) in entries {
+            let integrity = Some((*integrity).to_string()).filter(|i| !i.is_empty());
+            lockfile.entries.insert((*descriptor).to_string(), Entry { version: (*version).to_string(), integrity, ..Entry::default() });
+        }
+        lockfile
+    }
+
+    fn package(name: &str, version: &str) -> Package {
+        Package { name: name.to_string(), version: version.to_string() }
+    }
+
+    fn change(name: &str, from: &str, to: &str) -> VersionChange {
+        VersionChange { name: name.to_string(), from: from.to_string(), to: to.to_string() }
+    }
+
+    #[test]
+    fn diff_versions() {
+        let old = lockfile(&[("a@^1.0.0", "1.0.0", ""), ("a@^2.0.0", "2.0.0", ""), ("b@^1.0.0", "1.2.0", ""), ("c@^1.0.0", "1.0.0", ""), ("d@^1.0.0", "1.0.0", "")]);
+        let new = lockfile(&[("a@^1.0.0", "1.1.0", ""), ("a@^3.0.0", "3.0.0", ""), ("b@~1.1.0", "1.1.5", ""), ("d@^1.0.0", "1.0.0", ""), ("e@^1.0.0", "1.0.0", "")]);
+        let diff = diff(&old, &new);
+        assert_eq!(vec![package("e", "1.0.0")], diff.added);
+        assert_eq!(vec![package("c", "1.0.0")], diff.removed);
+        assert_eq!(vec![change("a", "1.0.0", "1.1.0"), change("a", "2.0.0", "3.0.0")], diff.upgraded);
+        assert_eq!(vec![change("b", "1.2.0", "1.1.5")], diff.downgraded);
+        assert_eq!(vec![Moved { descriptor: "a@^1.0.0".to_string(), from: "1.0.0".to_string(), to: "1.1.0".to_string() }], diff.moved);
+        assert!(diff.changed.is_empty());
+        assert!(super::diff(&new, &new).is_empty());
+        assert_eq!(vec![Some(Bump::Minor), Some(Bump::Major)], diff.upgraded.iter().map(VersionChange::bump).collect::<Vec<_>>());
+        assert_eq!(Some(Bump::Minor), diff.downgraded[0].bump());
+        assert_eq!(Some(Bump::Prerelease), change("a", "1.0.0-rc.1", "1.0.0").bump());
+        assert_eq!(None, change("a", "1.0.0+a", "1.0.0+b").bump());
+    }
+
+    #[test]
+    fn diff_sources() {
+        let old = lockfile(&[("a@^1.0.0", "1.0.0", "sha512-old"), ("b@^1.0.0", "1.0.0", "sha512-b")]);
+        let mut new = lockfile(&[("a@^1.0.0", "1.0.0", "sha512-new"), ("b@^1.0.0", "1.0.0", "sha512-b")]);
+        new.entries.get_mut("b@^1.0.0").unwrap().resolved = Some("https://evil.example.com/b-1.0.0.tgz".to_string());
+        let diff = diff(&old, &new);
+        let integrity = Some((Some("sha512-old".to_string()), Some("sha512-new".to_string())));
+        let resolved = Some((None, Some("https://evil.example.com/b-1.0.0.tgz".to_string())));
+        assert_eq!(
+            vec![
+                SourceChange { name: "a".to_string(), version: "1.0.0".to_string(), resolved: None, integrity },
+                SourceChange { name: "b".to_string(), version: "1.0.0".to_string(), resolved, integrity: None },
+            ],
+            diff.changed
+        );
+        assert!(diff.added.is_empty() && diff.upgraded.is_empty() && diff.moved.is_empty());
+    }
+
+    #[test]
+    fn diff_added_descriptor_sources() {
+        let entry = |resolved: &str| Entry { version: "1.0.0".to_string(), resolved: Some(resolved.to_string()), ..Entry::default() };
+        let registry = "https://registry.yarnpkg.com/a/-/a-1.0.0.tgz";
+        let evil = "https://evil.example.com/a-1.0.0.tgz";
+        let mut old = Lockfile::default();
+        old.entries.insert("a@^1.0.0".to_string(), entry(registry));
+        let mut new = old.clone();
+        new.entries.insert("a@~1.0.0".to_string(), entry(evil));
+        let resolved = Some((Some(registry.to_string()), Some(evil.to_string())));
+        assert_eq!(vec![SourceChange { name: "a".to_string(), version: "1.0.0".to_string(), resolved, integrity: None }], diff(&old, &new).changed);
+
+        // a descriptor switching to a source another descriptor already used
+        old.entries.insert("a@~1.0.0".to_string(), entry(evil));
+        new.entries.insert("a@^1.0.0".to_string(), entry(evil));
+        let resolved = Some((Some(registry.to_string()), Some(evil.to_string())));
+        assert_eq!(vec![SourceChange { name: "a".to_string(), version: "1.0.0".to_string(), resolved, integrity: None }], diff(&old, &new).changed);
+        assert!(diff(&new, &new).is_empty());
+    }
+}
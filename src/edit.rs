@@ -0,0 +1,411 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Formatter;
+use std::rc::Rc;
+
+use crate::cst::{Document, LineEnding, Node, NodeValue, Trivia};
+use crate::parser::Value;
+use crate::stringify::{maybe_wrap, priority_then_alpha_sort, sort_alpha};
+
+/// Edit error.
+///
+/// This error is returned when an edit cannot be applied. The document is left unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No entry has the descriptor.
+    DescriptorNotFound {
+        /// The descriptor.
+        descriptor: String,
+    },
+    /// An entry already has the descriptor.
+    DescriptorExists {
+        /// The descriptor.
+        descriptor: String,
+    },
+    /// The entry, or the field to edit in it, is not a mapping.
+    InvalidEntry {
+        /// The descriptor of the entry.
+        descriptor: String,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DescriptorNotFound { descriptor } => write!(f, "Edit error[{descriptor}]: descriptor not found"),
+            Self::DescriptorExists { descriptor } => write!(f, "Edit error[{descriptor}]: descriptor already exists"),
+            Self::InvalidEntry { descriptor } => write!(f, "Edit error[{descriptor}]: expected an object"),
+        }
+    }
+}
+
+/// Editing operations.
+///
+/// Edits only touch the lines they change. New lines are quoted and ordered the way yarn writes
+/// them, and use the line ending of the document. An entry whose descriptors change is moved to
+/// keep the entries sorted.
+impl Document {
+    /// Set the version of the entry the descriptor resolves to, along with its `resolved` and
+    /// `integrity` fields. A `None` removes the field.
+    ///
+    /// The change applies to every descriptor of the entry, e.g. to both `a@^1.0.0` and
+    /// `a@^1.2.0` for an entry written as `"a@^1.0.0", "a@^1.2.0":`.
+    ///
+    /// # Errors
+    /// - [`Error::DescriptorNotFound`]: When no entry has the descriptor
+    /// - [`Error::InvalidEntry`]: When the entry is not a mapping
+    pub fn set_resolution(&mut self, descriptor: &str, version: &str, resolved: Option<&str>, integrity: Option<&str>) -> Result<(), Error> {
+        let i = self.entry_index(descriptor)?;
+        self.edit(|doc, eol| {
+            let entry = &mut doc.nodes[i];
+            set_field(entry, "version", Some(version), eol, priority_then_alpha_sort);
+            set_field(entry, "resolved", resolved, eol, priority_then_alpha_sort);
+            set_field(entry, "integrity", integrity, eol, priority_then_alpha_sort);
+        });
+        Ok(())
+    }
+
+    /// Remove the entry the descriptor resolves to, with all of its descriptors.
+    ///
+    /// Comments directly above the entry are removed with it, the file header is kept.
+    ///
+    /// # Errors
+    /// - [`Error::DescriptorNotFound`]: When no entry has the descriptor
+    pub fn remove_entry(&mut self, descriptor: &str) -> Result<(), Error> {
+        let i = self.index_of(descriptor)?;
+        self.edit(|doc, _| {
+            doc.detach(i);
+        });
+        Ok(())
+    }
+
+    /// Replace a descriptor of an entry with another one.
+    ///
+    /// # Errors
+    /// - [`Error::DescriptorNotFound`]: When no entry has the descriptor
+    /// - [`Error::DescriptorExists`]: When an entry already has the new descriptor
+    /// - [`Error::InvalidEntry`]: When the entry is not a mapping
+    pub fn rename_descriptor(&mut self, from: &str, to: &str) -> Result<(), Error> {
+        let i = self.entry_index(from)?;
+        if from == to {
+            return Ok(());
+        }
+        self.check_absent(to)?;
+        self.edit(|doc, eol| {
+            let keys = doc.nodes[i].keys.iter().map(|k| if k == from { to.to_string() } else { k.clone() }).collect();
+            doc.set_keys(i, keys, eol);
+        });
+        Ok(())
+    }
+
+    /// Add a descriptor to the entry another descriptor resolves to.
+    ///
+    /// # Errors
+    /// - [`Error::DescriptorNotFound`]: When no entry has the existing descriptor
+    /// - [`Error::DescriptorExists`]: When an entry already has the new descriptor
+    /// - [`Error::InvalidEntry`]: When the entry is not a mapping
+    pub fn add_descriptor_alias(&mut self, existing: &str, alias: &str) -> Result<(), Error> {
+        let i = self.entry_index(existing)?;
+        self.check_absent(alias)?;
+        self.edit(|doc, eol| {
+            let mut keys = doc.nodes[i].keys.clone();
+            keys.push(alias.to_string());
+            doc.set_keys(i, keys, eol);
+        });
+        Ok(())
+    }
+
+    /// Set the range of a dependency of the entry the descriptor resolves to, adding the
+    /// `dependencies` field when missing.
+    ///
+    /// # Errors
+    /// - [`Error::DescriptorNotFound`]: When no entry has the descriptor
+    /// - [`Error::InvalidEntry`]: When the entry or its `dependencies` field is not a mapping
+    pub fn set_dependency(&mut self, descriptor: &str, name: &str, range: &str) -> Result<(), Error> {
+        let i = self.entry_index(descriptor)?;
+        if matches!(self.nodes[i].get("dependencies").map(Node::value), Some(NodeValue::Scalar(_))) {
+            return Err(Error::InvalidEntry { descriptor: descriptor.to_string() });
+        }
+        self.edit(|doc, eol| {
+            let entry = &mut doc.nodes[i];
+            let NodeValue::Mapping(children) = &mut entry.value else { unreachable!() };
+            let indent = entry.indent + 2;
+            let dependencies = children.iter().rposition(|c| has_key(c, "dependencies")).unwrap_or_else(|| {
+                let node = Node {
+                    leading: vec![],
+                    indent,
+                    raw: format!("{}:", maybe_wrap("dependencies")),
+                    eol,
+                    keys: vec!["dependencies".to_string()],
+                    value: NodeValue::Mapping(vec![]),
+                };
+                insert_child(children, node, priority_then_alpha_sort)
+            });
+            set_field(&mut children[dependencies], name, Some(range), eol, sort_alpha);
+        });
+        Ok(())
+    }
+
+    /// Run an edit, then give every line but the last a line ending, keeping whether the
+    /// document ends with one.
+    fn edit(&mut self, f: impl FnOnce(&mut Self, LineEnding)) {
+        let mut eols = vec![];
+        collect_line_endings(self, &mut eols);
+        let eol = eols.iter().map(|e| **e).find(|&e| e != LineEnding::None).unwrap_or_default();
+        let final_newline = eols.last().is_none_or(|e| **e != LineEnding::None);
+        f(self, eol);
+        let mut eols = vec![];
+        collect_line_endings(self, &mut eols);
+        for e in &mut eols {
+            if **e == LineEnding::None {
+                **e = eol;
+            }
+        }
+        if let (false, Some(last)) = (final_newline, eols.last_mut()) {
+            **last = LineEnding::None;
+        }
+    }
+
+    fn index_of(&self, descriptor: &str) -> Result<usize, Error> {
+        self.nodes.iter().rposition(|n| has_key(n, descriptor)).ok_or_else(|| Error::DescriptorNotFound { descriptor: descriptor.to_string() })
+    }
+
+    /// Find the entry having the descriptor, checking it is a mapping.
+    fn entry_index(&self, descriptor: &str) -> Result<usize, Error> {
+        let i = self.index_of(descriptor)?;
+        match self.nodes[i].value {
+            NodeValue::Mapping(_) => Ok(i),
+            NodeValue::Scalar(_) => Err(Error::InvalidEntry { descriptor: descriptor.to_string() }),
+        }
+    }
+
+    fn check_absent(&self, descriptor: &str) -> Result<(), Error> {
+        match self.index_of(descriptor) {
+            Ok(_) => Err(Error::DescriptorExists { descriptor: descriptor.to_string() }),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Replace the keys of a top-level node, moving it if it is no longer in sorted order.
+    fn set_keys(&mut self, i: usize, mut keys: Vec<String>, eol: LineEnding) {
+        keys.sort_by(|a, b| sort_alpha(a, b));
+        keys.dedup();
+        let node = &mut self.nodes[i];
+        node.raw = format!("{}:", keys.iter().map(|k| maybe_wrap(k)).collect::<Vec<_>>().join(", "));
+        node.keys = keys;
+        let sorted_after = |prev: &Node, next: &Node| sort_alpha(first_key(prev), first_key(next)) != Ordering::Greater;
+        let in_order = (i == 0 || sorted_after(&self.nodes[i - 1], &self.nodes[i]))
+            && self.nodes.get(i + 1).is_none_or(|next| sorted_after(&self.nodes[i], next));
+        if !in_order {
+            let node = self.detach(i);
+            self.insert_sorted(node, eol);
+        }
+    }
+
+    /// Remove a top-level node. The comments directly above it are kept on the node, the lines
+    /// separating it from the previous node are moved to the next node.
+    fn detach(&mut self, i: usize) -> Node {
+        let mut node = self.nodes.remove(i);
+        let mut leading = split_separator(&mut node.leading);
+        if let Some(next) = self.nodes.get_mut(i) {
+            // the blank lines separating the next node are replaced with ours
+            leading.extend(next.leading.drain(..).skip_while(is_blank));
+            next.leading = leading;
+        } else if !leading.iter().all(is_blank) {
+            self.trailing.splice(0..0, leading);
+        }
+        node
+    }
+
+    /// Insert a top-level node before the first node sorting after it, separated by a blank line.
+    fn insert_sorted(&mut self, mut node: Node, eol: LineEnding) {
+        let at = self.nodes.iter().position(|n| sort_alpha(first_key(n), first_key(&node)) == Ordering::Greater).unwrap_or(self.nodes.len());
+        let blank = Trivia { raw: String::new(), eol };
+        let mut leading = match self.nodes.get_mut(at) {
+            // the first node gives its header comments to the new first node
+            Some(first) if at == 0 => {
+                let header = split_separator(&mut first.leading);
+                first.leading.insert(0, blank);
+                header
+            }
+            _ if at == 0 => vec![],
+            _ => vec![blank],
+        };
+        leading.append(&mut node.leading);
+        node.leading = leading;
+        self.nodes.insert(at, node);
+    }
+}
+
+fn is_blank(trivia: &Trivia) -> bool {
+    trivia.raw.trim().is_empty()
+}
+
+/// Split off the leading trivia up to the last blank line, leaving the comments directly above
+/// the node.
+fn split_separator(leading: &mut Vec<Trivia>) -> Vec<Trivia> {
+    let split = leading.iter().rposition(is_blank).map_or(0, |p| p + 1);
+    leading.drain(..split).collect()
+}
+
+fn has_key(node: &Node, key: &str) -> bool {
+    node.keys.iter().any(|k| k == key)
+}
+
+fn first_key(node: &Node) -> &str {
+    node.keys.iter().min_by(|a, b| sort_alpha(a, b)).map_or("", String::as_str)
+}
+
+fn collect_line_endings<'d>(doc: &'d mut Document, out: &mut Vec<&'d mut LineEnding>) {
+    fn visit<'d>(node: &'d mut Node, out: &mut Vec<&'d mut LineEnding>) {
+        out.extend(node.leading.iter_mut().map(|t| &mut t.eol));
+        out.push(&mut node.eol);
+        if let NodeValue::Mapping(children) = &mut node.value {
+            for child in children {
+                visit(child, out);
+            }
+        }
+    }
+    for node in &mut doc.nodes {
+        visit(node, out);
+    }
+    out.extend(doc.trailing.iter_mut().map(|t| &mut t.eol));
+}
+
+/// Insert a node before the first child sorting after it, returning its index.
+fn insert_child(children: &mut Vec<Node>, node: Node, order: fn(&str, &str) -> Ordering) -> usize {
+    let at = children.iter().position(|c| order(first_key(c), first_key(&node)) == Ordering::Greater).unwrap_or(children.len());
+    children.insert(at, node);
+    at
+}
+
+/// Set or remove a scalar field of a mapping node.
+fn set_field(node: &mut Node, key: &str, value: Option<&str>, eol: LineEnding, order: fn(&str, &str) -> Ordering) {
+    let indent = node.indent + 2;
+    let NodeValue::Mapping(children) = &mut node.value else { return };
+    let scalar = |v: &str| NodeValue::Scalar(Value::String(Rc::new(v.to_string())));
+    match (children.iter().rposition(|c| has_key(c, key)), value) {
+        (Some(i), Some(v)) => {
+            let child = &mut children[i];
+            if child.value != scalar(v) {
+                child.raw = format!("{} {}", maybe_wrap(key), maybe_wrap(v));
+                child.keys = vec![key.to_string()];
+                child.value = scalar(v);
+            }
+        }
+        (Some(i), None) => {
+            let removed = children.remove(i);
+            if let Some(next) = children.get_mut(i) {
+                next.leading.splice(0..0, removed.leading);
+            }
+        }
+        (None, Some(v)) => {
+            let raw = format!("{} {}", maybe_wrap(key), maybe_wrap(v));
+            insert_child(children, Node { leading: vec![], indent, raw, eol, keys: vec![key.to_string()], value: scalar(v) }, order);
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Lockfile;
+    use crate::stringify::stringify;
+
+    const INPUT: &[u8] = include_bytes!("test.lock.1");
+
+    /// Apply the same change to a document and to the typed lockfile, and check the document is
+    /// written the way yarn would write the lockfile.
+    fn check(edit: impl FnOnce(&mut Document), expected: impl FnOnce(&mut Lockfile)) -> Document {
+        let mut doc = Document::parse(INPUT).unwrap();
+        edit(&mut doc);
+        let mut lockfile = Lockfile::parse(INPUT).unwrap();
+        expected(&mut lockfile);
+        assert_eq!(stringify(&lockfile.to_value()), doc.to_string());
+        doc
+    }
+
+    #[test]
+    fn set_resolution_of_multi_key_entry() {
+        let url = "https://registry.yarnpkg.com/@jridgewell/sourcemap-codec/-/sourcemap-codec-1.5.0.tgz#3188bcb273a414b0d215fd22a58540b989b9409a";
+        check(
+            |doc| doc.set_resolution("@jridgewell/sourcemap-codec@^1.4.14", "1.5.0", Some(url), None).unwrap(),
+            |lockfile| {
+                for key in ["@jridgewell/sourcemap-codec@^1.4.10", "@jridgewell/sourcemap-codec@^1.4.14"] {
+                    let entry = lockfile.entries.get_mut(key).unwrap();
+                    entry.version = "1.5.0".to_string();
+                    entry.resolved = Some(url.to_string());
+                    entry.integrity = None;
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn remove_and_rename_entries() {
+        check(
+            |doc| {
+                doc.remove_entry("@colors/colors@1.5.0").unwrap();
+                doc.rename_descriptor("@isaacs/cliui@^8.0.2", "@isaacs/cliui@^8.0.0").unwrap();
+                doc.rename_descriptor("@discoveryjs/json-ext@^0.5.0", "zzz@^0.5.0").unwrap();
+            },
+            |lockfile| {
+                lockfile.entries.remove("@colors/colors@1.5.0");
+                let entry = lockfile.entries.remove("@isaacs/cliui@^8.0.2").unwrap();
+                lockfile.entries.insert("@isaacs/cliui@^8.0.0".to_string(), entry);
+                let entry = lockfile.entries.remove("@discoveryjs/json-ext@^0.5.0").unwrap();
+                lockfile.entries.insert("zzz@^0.5.0".to_string(), entry);
+            },
+        );
+    }
+
+    #[test]
+    fn add_alias_and_dependencies() {
+        check(
+            |doc| {
+                doc.add_descriptor_alias("@types/estree@^1.0.5", "@types/estree@^1.0.0").unwrap();
+                doc.set_dependency("@colors/colors@1.5.0", "@types/estree", "^1.0.0").unwrap();
+                doc.set_dependency("@isaacs/cliui@^8.0.2", "strip-ansi", "^7.1.0").unwrap();
+                doc.set_dependency("@isaacs/cliui@^8.0.2", "a-new-dep", "1.0.0").unwrap();
+            },
+            |lockfile| {
+                let entry = lockfile.entries["@types/estree@^1.0.5"].clone();
+                lockfile.entries.insert("@types/estree@^1.0.0".to_string(), entry);
+                let deps = &mut lockfile.entries.get_mut("@colors/colors@1.5.0").unwrap().dependencies;
+                deps.insert("@types/estree".to_string(), "^1.0.0".to_string());
+                let deps = &mut lockfile.entries.get_mut("@isaacs/cliui@^8.0.2").unwrap().dependencies;
+                deps.insert("strip-ansi".to_string(), "^7.1.0".to_string());
+                deps.insert("a-new-dep".to_string(), "1.0.0".to_string());
+            },
+        );
+    }
+
+    #[test]
+    fn edits_keep_comments_and_line_endings() {
+        let input = "# header\r\n\r\n# about a\r\na@^1:\r\n  version \"1.0.0\"  # pinned\r\n  resolved \"x\"\r\n\r\nb@^1:\r\n  version \"1.0.0\"";
+        let mut doc = Document::parse(input.as_bytes()).unwrap();
+        doc.set_resolution("a@^1", "1.0.0", None, Some("sha1-abc")).unwrap();
+        assert_eq!("# header\r\n\r\n# about a\r\na@^1:\r\n  version \"1.0.0\"  # pinned\r\n  integrity sha1-abc\r\n\r\nb@^1:\r\n  version \"1.0.0\"", doc.to_string());
+        doc.set_dependency("b@^1", "a", "^1").unwrap();
+        assert_eq!("# header\r\n\r\n# about a\r\na@^1:\r\n  version \"1.0.0\"  # pinned\r\n  integrity sha1-abc\r\n\r\nb@^1:\r\n  version \"1.0.0\"\r\n  dependencies:\r\n    a \"^1\"", doc.to_string());
+        doc.rename_descriptor("a@^1", "c@^1").unwrap();
+        assert_eq!("# header\r\n\r\nb@^1:\r\n  version \"1.0.0\"\r\n  dependencies:\r\n    a \"^1\"\r\n\r\n# about a\r\nc@^1:\r\n  version \"1.0.0\"  # pinned\r\n  integrity sha1-abc", doc.to_string());
+        doc.remove_entry("b@^1").unwrap();
+        assert_eq!("# header\r\n\r\n# about a\r\nc@^1:\r\n  version \"1.0.0\"  # pinned\r\n  integrity sha1-abc", doc.to_string());
+    }
+
+    #[test]
+    fn edit_errors() {
+        let mut doc = Document::parse(b"a@^1:\n  version \"1.0.0\"\n  dependencies \"x\"\nb@^1:\n  version \"1.0.0\"\nc \"d\"\n").unwrap();
+        let before = doc.clone();
+        assert_eq!(Err(Error::DescriptorNotFound { descriptor: "x".to_string() }), doc.remove_entry("x"));
+        assert_eq!(Err(Error::DescriptorExists { descriptor: "b@^1".to_string() }), doc.rename_descriptor("a@^1", "b@^1"));
+        assert_eq!(Err(Error::DescriptorExists { descriptor: "a@^1".to_string() }), doc.add_descriptor_alias("b@^1", "a@^1"));
+        assert_eq!(Err(Error::InvalidEntry { descriptor: "a@^1".to_string() }), doc.set_dependency("a@^1", "x", "1"));
+        assert_eq!(Err(Error::InvalidEntry { descriptor: "c".to_string() }), doc.set_resolution("c", "1", None, None));
+        assert_eq!(before, doc);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::tokens::TokenKind;
+
+/// The kind of a lexing or parsing error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// An indentation with an odd number of spaces.
+    InvalidIndent {
+        /// The number of spaces found.
+        found: usize,
+    },
+    /// An indentation deeper than the enclosing mapping allows.
+    UnexpectedIndent {
+        /// The number of spaces expected.
+        expected: usize,
+        /// The number of spaces found.
+        found: usize,
+    },
+    /// A quoted string without a closing quote.
+    UnterminatedString,
+    /// A number that does not fit.
+    InvalidNumber,
+    /// A character that cannot start a token.
+    InvalidCharacter,
+    /// The input ended before the parser was done.
+    UnexpectedEndOfInput,
+    /// The `# yarn lockfile v` comment names a version this parser does not support.
+    UnsupportedVersion(i32),
+    /// A key was expected.
+    ExpectedKey,
+    /// A key after a comma was expected.
+    ExpectedString,
+    /// A colon after a key was expected.
+    ExpectedColon,
+    /// A token that is not allowed at this position.
+    UnexpectedToken(TokenKind),
+    /// A quoted string with an invalid escape sequence.
+    InvalidString,
+    /// Characters after a quoted value.
+    TrailingCharacters,
+    /// The input is not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidIndent { found } => write!(f, "Invalid number of spaces: {found}"),
+            Self::UnexpectedIndent { expected, found } => write!(f, "Unexpected indentation: expected {expected} spaces, found {found}"),
+            Self::UnterminatedString => write!(f, "Unexpected EOF"),
+            Self::InvalidNumber => write!(f, "Number parse failed"),
+            Self::InvalidCharacter => write!(f, "Invalid character"),
+            Self::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
+            Self::UnsupportedVersion(v) => write!(f, "Unsupported lockfile version: {v}"),
+            Self::ExpectedKey => write!(f, "Expected a key"),
+            Self::ExpectedString => write!(f, "Expected string"),
+            Self::ExpectedColon => write!(f, "Expected a colon"),
+            Self::UnexpectedToken(token) => write!(f, "Unexpected token {token:?}"),
+            Self::InvalidString => write!(f, "Invalid quoted string"),
+            Self::TrailingCharacters => write!(f, "Unexpected characters after string"),
+            Self::InvalidUtf8 => write!(f, "Invalid UTF-8 string"),
+        }
+    }
+}
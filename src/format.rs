@@ -0,0 +1,142 @@
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::berry;
+use crate::lockfile::{Error, Lockfile};
+use crate::parser::version_match;
+
+/// The lockfile dialect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// A yarn 1.x `yarn.lock`.
+    YarnV1,
+    /// A Yarn Berry (v2+) `yarn.lock`, with a `__metadata` block.
+    YarnBerry,
+    /// An npm `package-lock.json` or `npm-shrinkwrap.json`.
+    Npm,
+    /// A pnpm `pnpm-lock.yaml`.
+    Pnpm,
+}
+
+/// The detected dialect and version of a lockfile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Format {
+    /// The lockfile dialect.
+    pub dialect: Dialect,
+    /// The format version as written in the lockfile, e.g. `1` for `# yarn lockfile v1`,
+    /// the `__metadata` version for Berry, or `lockfileVersion` for npm and pnpm.
+    pub version: String,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.dialect {
+            Dialect::YarnV1 => write!(f, "yarn lockfile v{}", self.version),
+            Dialect::YarnBerry => write!(f, "yarn berry lockfile v{}", self.version),
+            Dialect::Npm => write!(f, "npm lockfileVersion {}", self.version),
+            Dialect::Pnpm => write!(f, "pnpm lockfileVersion {}", self.version),
+        }
+    }
+}
+
+/// Detect the dialect and version of the input lockfile.
+///
+/// Looks at the `# yarn lockfile v1` header comment, the Berry `__metadata` block, and the npm
+/// and pnpm `lockfileVersion` keys. Returns `None` when none of them are found.
+#[must_use]
+pub fn detect_format(input: &[u8]) -> Option<Format> {
+    let input = input.strip_prefix(b"\xef\xbb\xbf").unwrap_or(input);
+    if input.trim_ascii_start().starts_with(b"{") {
+        return detect_npm(input);
+    }
+    let mut lines = input.split(|&b| b == b'\n').map(|l| l.strip_suffix(b"\r").unwrap_or(l));
+    while let Some(line) = lines.next() {
+        if let Some(comment) = line.strip_prefix(b"#") {
+            if let Some(v) = version_match(comment) {
+                return Some(Format { dialect: Dialect::YarnV1, version: v.to_string() });
+            }
+        } else if let Some(v) = line.strip_prefix(b"lockfileVersion:") {
+            return Some(Format { dialect: Dialect::Pnpm, version: unquote_yaml_scalar(v) });
+        } else if line.trim_ascii_end() == format!("{}:", berry::METADATA_KEY).as_bytes() {
+            let version = lines
+                .take_while(|l| l.starts_with(b" "))
+                .find_map(|l| l.trim_ascii().strip_prefix(b"version:").map(unquote_yaml_scalar))
+                .unwrap_or_default();
+            return Some(Format { dialect: Dialect::YarnBerry, version });
+        }
+    }
+    None
+}
+
+/// Detect the format of the input lockfile and parse it with the matching parser.
+///
+/// # Errors
+/// - [`Error::UnknownFormat`]: When the format cannot be detected, see [`detect_format`]
+/// - [`Error::UnsupportedFormat`]: When no parser is available for the detected format
+/// - Any other [`Error`]: When parsing failed
+pub fn load(input: &[u8]) -> Result<Lockfile, Error> {
+    let format = detect_format(input).ok_or(Error::UnknownFormat)?;
+    match format.dialect {
+        Dialect::YarnV1 => Lockfile::parse(input),
+        Dialect::YarnBerry => Lockfile::parse_berry(input),
+        Dialect::Npm if matches!(format.version.as_str(), "1" | "2" | "3") => Lockfile::parse_npm(input),
+        Dialect::Npm | Dialect::Pnpm => Err(Error::UnsupportedFormat(format)),
+    }
+}
+
+fn detect_npm(input: &[u8]) -> Option<Format> {
+    const KEY: &[u8] = b"\"lockfileVersion\"";
+    let start = input.windows(KEY.len()).position(|w| w == KEY)? + KEY.len();
+    let rest = input[start..].trim_ascii_start().strip_prefix(b":")?.trim_ascii_start();
+    let len = rest.iter().position(|b| !b.is_ascii_digit()).unwrap_or(rest.len());
+    if len == 0 {
+        return None;
+    }
+    Some(Format { dialect: Dialect::Npm, version: String::from_utf8_lossy(&rest[..len]).into_owned() })
+}
+
+fn unquote_yaml_scalar(input: &[u8]) -> String {
+    let s = String::from_utf8_lossy(input.trim_ascii());
+    s.trim_matches(|c| c == '\'' || c == '"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(dialect: Dialect, version: &str) -> Format {
+        Format { dialect, version: version.to_string() }
+    }
+
+    #[test]
+    fn detect_yarn() {
+        assert_eq!(Some(format(Dialect::YarnV1, "1")), detect_format(include_bytes!("test.lock.1")));
+        assert_eq!(Some(format(Dialect::YarnBerry, "8")), detect_format(include_bytes!("test.berry.lock")));
+    }
+
+    #[test]
+    fn detect_npm_and_pnpm() {
+        let npm = b"{\r\n  \"name\": \"x\",\r\n  \"lockfileVersion\" : 3,\r\n  \"requires\": true\r\n}";
+        assert_eq!(Some(format(Dialect::Npm, "3")), detect_format(npm));
+        assert_eq!(Some(format(Dialect::Pnpm, "6.0")), detect_format(b"lockfileVersion: '6.0'\n\nsettings:\n"));
+        assert_eq!(Some(format(Dialect::Pnpm, "5.4")), detect_format(b"lockfileVersion: 5.4\n"));
+    }
+
+    #[test]
+    fn detect_unknown() {
+        assert_eq!(None, detect_format(b""));
+        assert_eq!(None, detect_format(b"{\"name\": \"x\"}"));
+        assert_eq!(None, detect_format(b"a:\n  version \"1\"\n"));
+    }
+
+    #[test]
+    fn load_dispatch() {
+        assert!(load(include_bytes!("test.lock.2")).unwrap().get("@babel/core@^7.12.3").is_some());
+        assert!(load(include_bytes!("test.berry.lock")).unwrap().metadata.is_some());
+        assert!(matches!(load(b"lockfileVersion: '9.0'\n"), Err(Error::UnsupportedFormat(Format { dialect: Dialect::Pnpm, .. }))));
+        assert!(load(b"{\"lockfileVersion\": 3, \"packages\": {}}").unwrap().is_empty());
+        assert!(matches!(load(b"{\"lockfileVersion\": 4}"), Err(Error::UnsupportedFormat(Format { dialect: Dialect::Npm, .. }))));
+        assert!(matches!(load(b"foo"), Err(Error::UnknownFormat)));
+        assert_eq!("npm lockfileVersion 3", format(Dialect::Npm, "3").to_string());
+    }
+}
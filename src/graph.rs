@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::descriptor::{Descriptor, RangeKind};
+use crate::lockfile::{Entry, Lockfile};
+
+/// The dependency graph of a lockfile.
+///
+/// Every node is a resolved package, the descriptors of a lockfile that map to equal entries with
+/// the same package name share one node. Every edge links a `dependencies` or
+/// `optionalDependencies` item to the node its `name@range` descriptor resolves to. Nodes are
+/// identified by their index, in the order of their first descriptor.
+#[derive(Clone, Debug)]
+pub struct DependencyGraph<'a> {
+    nodes: Vec<Node<'a>>,
+    edges: Vec<Edge<'a>>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+    descriptors: HashMap<&'a str, usize>,
+    errors: Vec<Error>,
+}
+
+/// A resolved package of a [`DependencyGraph`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node<'a> {
+    /// The index of the node in the graph.
+    pub id: usize,
+    /// The package name of the descriptors, which is the alias for `npm:` aliases.
+    pub name: String,
+    /// The descriptors resolving to the node, ordered.
+    pub descriptors: Vec<&'a str>,
+    /// The entry of the descriptors.
+    pub entry: &'a Entry,
+}
+
+/// The kind of an [`Edge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    /// From `dependencies`.
+    Regular,
+    /// From `optionalDependencies`.
+    Optional,
+}
+
+/// A dependency of a [`Node`], resolved to another node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edge<'a> {
+    /// The node that has the dependency.
+    pub from: usize,
+    /// The node the dependency resolves to.
+    pub to: usize,
+    /// The dependency name.
+    pub name: &'a str,
+    /// The dependency range.
+    pub range: &'a str,
+    pub kind: DependencyKind,
+}
+
+/// Graph error.
+///
+/// This error is reported for every dependency that does not resolve to an entry of the lockfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No descriptor of the lockfile matches the dependency.
+    Unresolved {
+        /// The first descriptor of the node that has the dependency.
+        descriptor: String,
+        /// The dependency as a descriptor, e.g. `foo@^1.0.0`.
+        dependency: String,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unresolved { descriptor, dependency } => write!(f, "Graph error[{descriptor}]: unresolved dependency `{dependency}`"),
+        }
+    }
+}
+
+impl<'a> DependencyGraph<'a> {
+    /// Build the graph of a lockfile.
+    ///
+    /// Dependencies that cannot be resolved are left out of the graph and reported by
+    /// [`DependencyGraph::errors`].
+    #[must_use]
+    pub fn new(lockfile: &'a Lockfile) -> Self {
+        let mut graph = Self { nodes: vec![], edges: vec![], outgoing: vec![], incoming: vec![], descriptors: HashMap::new(), errors: vec![] };
+        let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+        for (descriptor, entry) in lockfile.iter() {
+            let name = Descriptor::parse(descriptor).name;
+            let ids = by_name.entry(name.clone()).or_default();
+            let id = if let Some(&id) = ids.iter().find(|&&id| graph.nodes[id].entry == entry) {
+                id
+            } else {
+                let id = graph.nodes.len();
+                graph.nodes.push(Node { id, name, descriptors: vec![], entry });
+                ids.push(id);
+                id
+            };
+            graph.nodes[id].descriptors.push(descriptor);
+            graph.descriptors.insert(descriptor, id);
+        }
+        graph.outgoing = vec![vec![]; graph.nodes.len()];
+        graph.incoming = vec![vec![]; graph.nodes.len()];
+        for from in 0..graph.nodes.len() {
+            let entry = graph.nodes[from].entry;
+            let dependencies = entry.dependencies.iter().map(|d| (d, DependencyKind::Regular));
+            let optional_dependencies = entry.optional_dependencies.iter().map(|d| (d, DependencyKind::Optional));
+            for ((name, range), kind) in dependencies.chain(optional_dependencies) {
+                let Some(to) = graph.resolve(name, range) else {
+                    let descriptor = graph.nodes[from].descriptors[0].to_string();
+                    graph.errors.push(Error::Unresolved { descriptor, dependency: format!("{name}@{range}") });
+                    continue;
+                };
+                graph.outgoing[from].push(graph.edges.len());
+                graph.incoming[to].push(graph.edges.len());
+                graph.edges.push(Edge { from, to, name, range, kind });
+            }
+        }
+        graph
+    }
+
+    /// Find the node a dependency resolves to. Berry writes ranges without a protocol as `npm:`
+    /// ranges in the descriptors.
+    #[must_use]
+    pub fn resolve(&self, name: &str, range: &str) -> Option<usize> {
+        self.find(&format!("{name}@{range}")).or_else(|| {
+            let descriptor = Descriptor { name: name.to_string(), range: range.to_string(), has_version: true };
+            matches!(descriptor.kind(), RangeKind::Semver | RangeKind::Tag).then(|| self.find(&format!("{name}@npm:{range}")))?
+        })
+    }
+
+    /// Find the node of a descriptor of the lockfile.
+    #[must_use]
+    pub fn find(&self, descriptor: &str) -> Option<usize> {
+        self.descriptors.get(descriptor).copied()
+    }
+
+    /// Get a node by its index.
+    #[must_use]
+    pub fn node(&self, id: usize) -> Option<&Node<'a>> {
+        self.nodes.get(id)
+    }
+
+    /// Iterate over the nodes, ordered by index.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node<'a>> {
+        self.nodes.iter()
+    }
+
+    /// Iterate over the nodes of a package name.
+    pub fn nodes_named<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s Node<'a>> {
+        self.nodes.iter().filter(move |node| node.name == name)
+    }
+
+    /// Iterate over the edges, ordered by the node they come from.
+    pub fn edges(&self) -> impl Iterator<Item = &Edge<'a>> {
+        self.edges.iter()
+    }
+
+    /// Iterate over the edges from a node to its dependencies.
+    pub fn dependencies(&self, id: usize) -> impl Iterator<Item = &Edge<'a>> {
+        self.outgoing.get(id).into_iter().flatten().map(|&i| &self.edges[i])
+    }
+
+    /// Iterate over the edges from the dependents of a node to it.
+    pub fn dependents(&self, id: usize) -> impl Iterator<Item = &Edge<'a>> {
+        self.incoming.get(id).into_iter().flatten().map(|&i| &self.edges[i])
+    }
+
+    /// The dependencies that could not be resolved.
+    #[must_use]
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// The number of nodes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the graph has no nodes.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_graph() {
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        let graph = DependencyGraph::new(&lockfile);
+        assert_eq!(Vec::<Error>::new(), graph.errors());
+        assert_eq!(lockfile.len(), graph.nodes().map(|node| node.descriptors.len()).sum::<usize>());
+        let edges: usize = lockfile.iter().map(|(_, e)| e.dependencies.len() + e.optional_dependencies.len()).sum();
+        assert!(graph.len() < lockfile.len() && graph.edges().count() < edges);
+
+        let trace_mapping = graph.find("@jridgewell/trace-mapping@^0.3.24").unwrap();
+        assert_eq!(Some(trace_mapping), graph.find("@jridgewell/trace-mapping@^0.3.25"));
+        assert_eq!(vec![trace_mapping], graph.nodes_named("@jridgewell/trace-mapping").map(|node| node.id).collect::<Vec<_>>());
+        let source_map = graph.find("@jridgewell/source-map@^0.3.3").unwrap();
+        assert!(graph.dependents(trace_mapping).any(|edge| edge.from == source_map && edge.range == "^0.3.25"));
+        assert!(graph.dependencies(source_map).all(|edge| edge.from == source_map && edge.kind == DependencyKind::Regular));
+
+        // aliases resolve by the alias name
+        let cliui = graph.find("@isaacs/cliui@^8.0.2").unwrap();
+        let edge = graph.dependencies(cliui).find(|edge| edge.name == "string-width-cjs").unwrap();
+        assert_eq!("string-width-cjs", graph.node(edge.to).unwrap().name);
+    }
+
+    #[test]
+    fn unresolved_dependencies() {
+        let mut lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        let entry = lockfile.entries.get_mut("@colors/colors@1.5.0").unwrap();
+        entry.optional_dependencies.insert("missing".to_string(), "^1.0.0".to_string());
+        let graph = DependencyGraph::new(&lockfile);
+        let error = Error::Unresolved { descriptor: "@colors/colors@1.5.0".to_string(), dependency: "missing@^1.0.0".to_string() };
+        assert_eq!([error], graph.errors());
+        assert_eq!("Graph error[@colors/colors@1.5.0]: unresolved dependency `missing@^1.0.0`", graph.errors()[0].to_string());
+    }
+
+    #[test]
+    fn build_berry_graph() {
+        let lockfile = Lockfile::parse_berry(include_bytes!("test.berry.lock")).unwrap();
+        let graph = DependencyGraph::new(&lockfile);
+        let code_frame = graph.find("@babel/code-frame@npm:^7.24.7").unwrap();
+        let picocolors = graph.resolve("picocolors", "^1.0.0").unwrap();
+        assert_eq!(Some(picocolors), graph.resolve("picocolors", "npm:^1.0.0"));
+        assert!(graph.dependencies(code_frame).any(|edge| edge.to == picocolors));
+    }
+}
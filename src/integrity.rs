@@ -0,0 +1,234 @@
+use std::fmt;
+use std::fmt::Formatter;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::digest::{base64_decode, base64_encode, hex_decode, Digest, Sha1, Sha256, Sha512};
+
+/// A hash algorithm allowed in an integrity value, ordered from weakest to strongest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    /// The name used as the prefix of a hash, e.g. `sha512`.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    const fn digest_len(self) -> usize {
+        match self {
+            Self::Sha1 => 20,
+            Self::Sha256 => 32,
+            Self::Sha384 => 48,
+            Self::Sha512 => 64,
+        }
+    }
+
+    const fn digest(self) -> Digest {
+        match self {
+            Self::Sha1 => Digest::Sha1(Sha1::new()),
+            Self::Sha256 => Digest::Sha256(Sha256::new()),
+            Self::Sha384 => Digest::Sha384(Sha512::new_384()),
+            Self::Sha512 => Digest::Sha512(Sha512::new()),
+        }
+    }
+}
+
+/// A single hash of an integrity value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Hash {
+    pub algorithm: Algorithm,
+    /// The raw digest bytes.
+    pub digest: Vec<u8>,
+}
+
+/// A [Subresource Integrity](https://www.w3.org/TR/SRI/) value, e.g. `sha512-...==`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Integrity {
+    /// The hashes, in input order.
+    pub hashes: Vec<Hash>,
+}
+
+/// Integrity error.
+///
+/// This error is returned when the input is not a valid integrity value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid integrity: {}", self.input)
+    }
+}
+
+impl Integrity {
+    /// Parse a whitespace-separated list of `<algorithm>-<base64>` hashes.
+    ///
+    /// Like the SRI specification, hashes with an unknown algorithm and `?` options are ignored.
+    ///
+    /// # Errors
+    /// - [`Error`]: When a hash is malformed, or no hash has a known algorithm
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let err = || Error { input: input.to_string() };
+        let mut hashes = vec![];
+        for token in input.split_ascii_whitespace() {
+            let (name, rest) = token.split_once('-').ok_or_else(err)?;
+            let Some(algorithm) = [Algorithm::Sha1, Algorithm::Sha256, Algorithm::Sha384, Algorithm::Sha512].into_iter().find(|a| a.name() == name) else {
+                continue;
+            };
+            let encoded = rest.split_once('?').map_or(rest, |(encoded, _)| encoded);
+            let digest = base64_decode(encoded).filter(|d| d.len() == algorithm.digest_len()).ok_or_else(err)?;
+            hashes.push(Hash { algorithm, digest });
+        }
+        if hashes.is_empty() {
+            return Err(err());
+        }
+        Ok(Self { hashes })
+    }
+
+    /// Build an integrity value from a hex sha1, like the legacy `#<sha1>` fragment of a
+    /// `resolved` URL.
+    ///
+    /// # Errors
+    /// - [`Error`]: When the input is not a hex sha1
+    pub fn from_sha1_hex(input: &str) -> Result<Self, Error> {
+        let digest = hex_decode(input).filter(|d| d.len() == Algorithm::Sha1.digest_len()).ok_or_else(|| Error { input: input.to_string() })?;
+        Ok(Self { hashes: vec![Hash { algorithm: Algorithm::Sha1, digest }] })
+    }
+
+    /// The strongest algorithm of the hashes.
+    #[must_use]
+    pub fn strongest(&self) -> Algorithm {
+        self.hashes.iter().map(|h| h.algorithm).max().unwrap_or(Algorithm::Sha1)
+    }
+
+    /// Check the data matches any hash of the strongest algorithm.
+    #[must_use]
+    pub fn verify(&self, data: &[u8]) -> bool {
+        let algorithm = self.strongest();
+        let mut digest = algorithm.digest();
+        digest.update(data);
+        self.matches(algorithm, &digest.finish())
+    }
+
+    /// Check the data read to the end matches any hash of the strongest algorithm.
+    ///
+    /// # Errors
+    /// - [`io::Error`]: When reading failed
+    pub fn verify_reader(&self, mut reader: impl Read) -> io::Result<bool> {
+        let algorithm = self.strongest();
+        let mut digest = algorithm.digest();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => digest.update(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(self.matches(algorithm, &digest.finish()))
+    }
+
+    /// Check the file content matches any hash of the strongest algorithm.
+    ///
+    /// # Errors
+    /// - [`io::Error`]: When the file cannot be read
+    pub fn verify_file(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        self.verify_reader(File::open(path)?)
+    }
+
+    fn matches(&self, algorithm: Algorithm, digest: &[u8]) -> bool {
+        self.hashes.iter().any(|h| h.algorithm == algorithm && h.digest == digest)
+    }
+}
+
+impl FromStr for Integrity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.algorithm.name(), base64_encode(&self.digest))
+    }
+}
+
+impl fmt::Display for Integrity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, hash) in self.hashes.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{hash}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // echo -n "hello" | openssl dgst -sha512 -binary | base64
+    const HELLO_SHA512: &str = "sha512-m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw==";
+    const HELLO_SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+
+    #[test]
+    fn parse_and_display() {
+        let integrity = Integrity::parse(HELLO_SHA512).unwrap();
+        assert_eq!(Algorithm::Sha512, integrity.strongest());
+        assert_eq!(HELLO_SHA512, integrity.to_string());
+
+        let integrity = Integrity::parse(&format!("sha1-qvTGHdzF6KLavt4PO0gs2a6pQ00= md5-XUFAKrxLKna5cZ2REBfFkg== {HELLO_SHA512}?opt")).unwrap();
+        assert_eq!(2, integrity.hashes.len());
+        assert_eq!(Algorithm::Sha512, integrity.strongest());
+        assert_eq!(Integrity::from_sha1_hex(HELLO_SHA1).unwrap().hashes[0], integrity.hashes[0]);
+
+        for input in ["", "md5-XUFAKrxLKna5cZ2REBfFkg==", "sha512", "sha512-!!", "sha1-qvTGHdzF6KLavt4PO0gs2a6p"] {
+            assert_eq!(Err(Error { input: input.to_string() }), Integrity::parse(input), "{input}");
+        }
+        assert!(Integrity::from_sha1_hex("aaf4").is_err());
+    }
+
+    #[test]
+    fn verify() {
+        let integrity = Integrity::parse(HELLO_SHA512).unwrap();
+        assert!(integrity.verify(b"hello"));
+        assert!(!integrity.verify(b"hello!"));
+        assert!(integrity.verify_reader(&b"hello"[..]).unwrap());
+
+        // the strongest algorithm decides, a matching weaker hash is not enough
+        let integrity = Integrity::parse(&format!("sha1-qvTGHdzF6KLavt4PO0gs2a6pQ00= sha256-{}", "A".repeat(43) + "=")).unwrap();
+        assert!(!integrity.verify(b"hello"));
+        assert!(Integrity::from_sha1_hex(HELLO_SHA1).unwrap().verify(b"hello"));
+
+        let path = std::env::temp_dir().join(format!("yarnlock-integrity-{}", std::process::id()));
+        std::fs::write(&path, b"hello").unwrap();
+        let result = Integrity::parse(HELLO_SHA512).unwrap().verify_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap());
+    }
+}
@@ -0,0 +1,340 @@
+//! A minimal JSON reader and writer for `package.json` and `package-lock.json` files, to keep the
+//! crate free of dependencies.
+
+use std::fmt::Write;
+
+/// A JSON value, objects keep the key order of the input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// The number as written in the input.
+    Number(String),
+    String(String),
+    Array(Vec<Self>),
+    Object(Vec<(String, Self)>),
+}
+
+impl Json {
+    /// Parse a JSON document, a leading BOM is skipped.
+    ///
+    /// Returns the byte offset of the error when the input is not valid JSON.
+    pub fn parse(input: &[u8]) -> Result<Self, usize> {
+        let mut parser = Parser { input, pos: 0 };
+        if input.starts_with(b"\xef\xbb\xbf") {
+            parser.pos = 3;
+        }
+        let value = parser.value(0)?;
+        parser.whitespace();
+        if parser.pos < input.len() {
+            return Err(parser.pos);
+        }
+        Ok(value)
+    }
+
+    /// Get the value of a key, when the value is an object.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Write the value like `JSON.stringify(value, null, 2)`.
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| {
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+        };
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Self::Number(n) => out.push_str(n),
+            Self::String(s) => write_string(out, s),
+            Self::Array(items) if items.is_empty() => out.push_str("[]"),
+            Self::Object(members) if members.is_empty() => out.push_str("{}"),
+            Self::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    indent(out, depth + 1);
+                    item.write(out, depth + 1);
+                }
+                indent(out, depth);
+                out.push(']');
+            }
+            Self::Object(members) => {
+                out.push('{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    indent(out, depth + 1);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write(out, depth + 1);
+                }
+                indent(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{0008}' => out.push_str("\\b"),
+            '\u{000c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Nesting deeper than this is rejected, to bound the recursion.
+const MAX_DEPTH: usize = 512;
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), usize> {
+        self.whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.pos);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &[u8], value: Json) -> Result<Json, usize> {
+        if !self.input[self.pos..].starts_with(literal) {
+            return Err(self.pos);
+        }
+        self.pos += literal.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, usize> {
+        self.whitespace();
+        if depth > MAX_DEPTH {
+            return Err(self.pos);
+        }
+        match self.peek() {
+            Some(b'n') => self.literal(b"null", Json::Null),
+            Some(b't') => self.literal(b"true", Json::Bool(true)),
+            Some(b'f') => self.literal(b"false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => break,
+                        _ => return Err(self.pos),
+                    }
+                }
+                self.pos += 1;
+                Ok(Json::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = vec![];
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.pos);
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    members.push((key, self.value(depth + 1)?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => break,
+                        _ => return Err(self.pos),
+                    }
+                }
+                self.pos += 1;
+                Ok(Json::Object(members))
+            }
+            _ => Err(self.pos),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, usize> {
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            let from = p.pos;
+            while p.peek().is_some_and(|c| c.is_ascii_digit()) {
+                p.pos += 1;
+            }
+            if p.pos == from { Err(p.pos) } else { Ok(()) }
+        };
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+        } else {
+            digits(self)?;
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digits(self)?;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            digits(self)?;
+        }
+        Ok(Json::Number(String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()))
+    }
+
+    fn string(&mut self) -> Result<String, usize> {
+        let open = self.pos;
+        self.pos += 1;
+        let mut buffer = vec![];
+        loop {
+            let start = self.pos;
+            let c = self.peek().ok_or(self.pos)?;
+            self.pos += 1;
+            match c {
+                b'"' => return String::from_utf8(buffer).map_err(|_| open),
+                b'\\' => {
+                    let c = self.peek().ok_or(self.pos)?;
+                    self.pos += 1;
+                    let unescaped = match c {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{0008}',
+                        b'f' => '\u{000c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape().ok_or(start)?,
+                        _ => return Err(start),
+                    };
+                    buffer.extend_from_slice(unescaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                0..0x20 => return Err(start),
+                _ => buffer.push(c),
+            }
+        }
+    }
+
+    /// The code point of a `\u` escape, a high surrogate must be followed by an escaped low surrogate.
+    fn unicode_escape(&mut self) -> Option<char> {
+        let hex = |p: &mut Self| {
+            let digits = std::str::from_utf8(p.input.get(p.pos..p.pos + 4)?).ok()?;
+            let code = u32::from_str_radix(digits, 16).ok().filter(|_| digits.bytes().all(|c| c.is_ascii_hexdigit()))?;
+            p.pos += 4;
+            Some(code)
+        };
+        let code = hex(self)?;
+        if (0xd800..0xdc00).contains(&code) {
+            if !self.input[self.pos..].starts_with(b"\\u") {
+                return None;
+            }
+            self.pos += 2;
+            let low = hex(self).filter(|low| (0xdc00..0xe000).contains(low))?;
+            return char::from_u32(0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00));
+        }
+        char::from_u32(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_json() {
+        let json = Json::parse(b"\xef\xbb\xbf{ \"a\": [1, -2.5e+3, true, false, null], \"b\": {\"c\": \"\\u00e9\\ud83d\\ude00\\n\"}, \"a\": {} }").unwrap();
+        let number = |s: &str| Json::Number(s.to_string());
+        assert_eq!(Some(&Json::Array(vec![number("1"), number("-2.5e+3"), Json::Bool(true), Json::Bool(false), Json::Null])), json.get("a"));
+        assert_eq!(Some("\u{e9}\u{1f600}\n"), json.get("b").and_then(|b| b.get("c")).and_then(Json::as_str));
+        assert_eq!(Ok(Json::Array(vec![])), Json::parse(b" [ ] "));
+    }
+
+    #[test]
+    fn write_json() {
+        let input = "{\n  \"a\": [\n    1,\n    \"\\\"\\\\\\n\\u0001\u{e9}\"\n  ],\n  \"b\": {},\n  \"c\": [],\n  \"d\": {\n    \"e\": null\n  }\n}";
+        assert_eq!(input, Json::parse(input.as_bytes()).unwrap().to_pretty_string());
+        assert_eq!("true", Json::Bool(true).to_pretty_string());
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (input, offset) in [
+            (&b""[..], 0),
+            (b"{\"a\" 1}", 5),
+            (b"{\"a\": 1,}", 8),
+            (b"[01]", 2),
+            (b"[1.]", 3),
+            (b"\"\\x\"", 1),
+            (b"\"\\ud83d\"", 1),
+            (b"\"a\nb\"", 2),
+            (b"{} x", 3),
+            (b"tru", 0),
+            (b"\"\xff\"", 0),
+        ] {
+            assert_eq!(Err(offset), Json::parse(input), "{}", String::from_utf8_lossy(input));
+        }
+        assert!(Json::parse(&[b'['; MAX_DEPTH + 2]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::tokens::{Token, TokenWrapper};
+use crate::error::ErrorKind;
+use crate::tokens::{Span, Token, TokenWrapper};
 
 fn index_of_char(input: &[u8], start: usize, target: u8) -> Result<usize, ()> {
     input[start..].iter().position(|&x| x == target).map_or(Err(()), |v| Ok(v + start))
@@ -8,7 +9,7 @@ fn measure_indent_len(input: &[u8]) -> usize {
     input.iter().position(|&x| x != b' ').unwrap_or(input.len())
 }
 
-fn measure_quoted_string(input: &[u8]) -> Result<usize, &'static str> {
+fn measure_quoted_string(input: &[u8]) -> Result<usize, ErrorKind> {
     assert_eq!(input[0], b'\"');
     for i in 1..input.len() {
         if input[i] == b'\"' {
@@ -18,13 +19,13 @@ fn measure_quoted_string(input: &[u8]) -> Result<usize, &'static str> {
             }
         }
     }
-    Err("Unexpected EOF")
+    Err(ErrorKind::UnterminatedString)
 }
 
-fn parse_number(input: &[u8]) -> Result<(i64, usize), &'static str> {
+fn parse_number(input: &[u8]) -> Result<(i64, usize), ErrorKind> {
     let end = input.iter().position(|x| !x.is_ascii_digit()).unwrap_or(input.len());
     let s = std::str::from_utf8(&input[..end]).unwrap();
-    s.parse::<i64>().map_or(Err("Number parse failed"), |v| Ok((v, end)))
+    s.parse::<i64>().map_or(Err(ErrorKind::InvalidNumber), |v| Ok((v, end)))
 }
 
 fn match_str_prefix(input: &[u8], prefix: &str) -> bool {
@@ -37,129 +38,169 @@ fn measure_unquoted_string(input: &[u8]) -> usize {
     input.iter().position(|&x| x == b':' || x == b' ' || x == b'\n' || x == b'\r' || x == b',').unwrap_or(input.len())
 }
 
-/// Tokenize the input yarn lock data.
+/// Tokenize the input yarn lock data lazily, yielding one token at a time.
 ///
 /// Translated from [https://github.com/yarnpkg/yarn/blob/master/src/lockfile/parse.js#L50](https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/src/lockfile/parse.js#L50)
-#[allow(clippy::cast_precision_loss)]
-#[allow(clippy::too_many_lines)]
-pub fn tokenize(input: &[u8]) -> Result<Vec<TokenWrapper>, Error> {
-    let mut input = input;
-    let mut line = 1;
-    let mut col = 0;
-    let mut last_new_line = true;
-    let mut tokens: Vec<TokenWrapper> = vec![];
-
-    macro_rules! commit {
-        ($t: expr) => {tokens.push(TokenWrapper { col, line, token:$t })};
+///
+/// The last token is always [`Token::Eof`], the iterator ends after it or after the first error.
+#[derive(Clone)]
+pub struct Lexer<'t> {
+    input: &'t [u8],
+    line: i32,
+    col: i32,
+    offset: usize,
+    span: Span,
+    last_new_line: bool,
+    done: bool,
+}
+
+impl<'t> Lexer<'t> {
+    pub const fn new(input: &'t [u8]) -> Self {
+        Self { input, line: 1, col: 0, offset: 0, span: Span { start: 0, end: 0 }, last_new_line: true, done: false }
     }
-    macro_rules! error {
-        ($reason: expr) => {return Err(Error { line, col, reason: $reason });};
+
+    /// The byte span of the last token yielded.
+    pub const fn span(&self) -> Span {
+        self.span
     }
-    while !input.is_empty() {
-        let mut chop = 0;
-        let ch = input[0];
-        match ch {
-            b'\r' | b'\n' => {
-                commit!(Token::NewLine);
-                chop += 1;
-                if input.len() > 1 && input[1] == b'\n' {
+}
+
+impl<'t> Iterator for Lexer<'t> {
+    type Item = Result<TokenWrapper<'t>, Error>;
+
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::too_many_lines)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        macro_rules! error {
+            ($kind: expr, $len: expr) => {{
+                self.done = true;
+                let span = Span { start: self.offset, end: self.offset + $len };
+                return Some(Err(Error { span, kind: $kind }));
+            }};
+        }
+        while !self.input.is_empty() {
+            let input = self.input;
+            let mut chop = 0;
+            let ch = input[0];
+            let token = match ch {
+                b'\r' | b'\n' => {
                     chop += 1;
+                    if input.len() > 1 && input[1] == b'\n' {
+                        chop += 1;
+                    }
+                    let token = TokenWrapper { col: self.col, line: self.line, token: Token::NewLine };
+                    self.span = Span { start: self.offset, end: self.offset + chop };
+                    self.line += 1;
+                    self.col = 0;
+                    self.offset += chop;
+                    self.input = &input[chop..];
+                    self.last_new_line = true;
+                    return Some(Ok(token));
                 }
-                line += 1;
-                col = 0;
-                input = &input[chop..];
-                last_new_line = true;
-                continue;
-            }
-            b'#' => {
-                let next_new_line = match index_of_char(input, 1, b'\n') {
-                    Ok(idx) => { idx }
-                    Err(()) => { input.len() }
-                };
-                commit!(Token::Comment(&input[1..next_new_line]));
-                chop += next_new_line;
-            }
-            b' ' => {
-                if last_new_line {
-                    let indent_size = measure_indent_len(input);
-                    if indent_size % 2 != 0 {
-                        error!("Invalid number of spaces");
+                b'#' => {
+                    let next_new_line = match index_of_char(input, 1, b'\n') {
+                        Ok(idx) => { idx }
+                        Err(()) => { input.len() }
+                    };
+                    chop += next_new_line;
+                    Some(Token::Comment(&input[1..next_new_line]))
+                }
+                b' ' => {
+                    if self.last_new_line {
+                        let indent_size = measure_indent_len(input);
+                        if indent_size.is_multiple_of(2) {
+                            chop += indent_size;
+                            Some(Token::Indent(indent_size))
+                        } else {
+                            error!(ErrorKind::InvalidIndent { found: indent_size }, indent_size);
+                        }
                     } else {
-                        commit!(Token::Indent(indent_size));
-                        chop += indent_size;
+                        chop += 1;
+                        None
+                    }
+                }
+                b'"' => {
+                    match measure_quoted_string(input) {
+                        Ok(len) => {
+                            chop += len;
+                            Some(Token::String(&input[..len]))
+                        }
+                        Err(kind) => {
+                            error!(kind, input.len());
+                        }
                     }
-                } else {
+                }
+                b':' => {
                     chop += 1;
+                    Some(Token::Colon)
                 }
-            }
-            b'"' => {
-                match measure_quoted_string(input) {
-                    Ok(len) => {
-                        commit!(Token::String(&input[..len]));
+                b',' => {
+                    chop += 1;
+                    Some(Token::Comma)
+                }
+                _ => {
+                    if match_str_prefix(input, "true") {
+                        chop += 4;
+                        Some(Token::Bool(true))
+                    } else if match_str_prefix(input, "false") {
+                        chop += 5;
+                        Some(Token::Bool(false))
+                    } else if ch.is_ascii_digit() {
+                        match parse_number(input) {
+                            Ok((n, len)) => {
+                                chop += len;
+                                Some(Token::Number(n as f64))
+                            }
+                            Err(kind) => {
+                                error!(kind, 1);
+                            }
+                        }
+                    } else if ch.is_ascii_alphabetic() || ch == b'/' || ch == b'.' || ch == b'_' || ch == b'-' {
+                        let len = measure_unquoted_string(input);
                         chop += len;
-                    }
-                    Err(reason) => {
-                        error!(reason);
+                        Some(Token::String(&input[..len]))
+                    } else {
+                        Some(Token::Invalid)
                     }
                 }
+            };
+            if chop == 0 {
+                error!(ErrorKind::InvalidCharacter, 1);
             }
-            b':' => {
-                commit!(Token::Colon);
-                chop += 1;
+            let wrapper = token.map(|token| TokenWrapper { col: self.col, line: self.line, token });
+            if wrapper.is_some() {
+                self.span = Span { start: self.offset, end: self.offset + chop };
             }
-            b',' => {
-                commit!(Token::Comma);
-                chop += 1;
-            }
-            _ => {
-                if match_str_prefix(input, "true") {
-                    commit!(Token::Bool(true));
-                    chop += 4;
-                } else if match_str_prefix(input, "false") {
-                    commit!(Token::Bool(false));
-                    chop += 5;
-                } else if ch.is_ascii_digit() {
-                    match parse_number(input) {
-                        Ok((n, len)) => {
-                            commit!(Token::Number(n as f64));
-                            chop += len;
-                        }
-                        Err(reason) => {
-                            error!(reason);
-                        }
-                    }
-                } else if ch.is_ascii_alphabetic() || ch == b'/' || ch == b'.' || ch == b'_' || ch == b'-' {
-                    let len = measure_unquoted_string(input);
-                    commit!(Token::String(&input[..len]));
-                    chop += len;
-                } else {
-                    commit!(Token::Invalid);
-                }
+            self.last_new_line = false;
+            self.col += i32::try_from(chop).unwrap();
+            self.offset += chop;
+            self.input = &input[chop..];
+            if let Some(wrapper) = wrapper {
+                return Some(Ok(wrapper));
             }
         }
-        if chop == 0 {
-            error!("infinite");
-        }
-        last_new_line = false;
-        col += i32::try_from(chop).unwrap();
-        input = &input[chop..];
+        self.done = true;
+        self.span = Span { start: self.offset, end: self.offset };
+        Some(Ok(TokenWrapper { col: self.col, line: self.line, token: Token::Eof }))
     }
-    commit!(Token::Eof);
-    Ok(tokens)
 }
 
 #[derive(Debug)]
 pub struct Error {
-    pub line: i32,
-    pub col: i32,
-    pub reason: &'static str,
+    pub span: Span,
+    pub kind: ErrorKind,
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::min;
 
-    use crate::lexer::tokenize;
+    use crate::error::ErrorKind;
+    use crate::lexer::Lexer;
+    use crate::tokens::Span;
     use crate::tokens::Token::*;
     use crate::tokens::TokenWrapper;
 
@@ -236,8 +277,22 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
-    fn do_test(input: &[u8]) -> Vec<TokenWrapper> {
-        let v = tokenize(input).unwrap();
+    #[test]
+    fn test_tokenize_lazily() {
+        let mut lexer = Lexer::new(b"a \"b\n");
+        assert_eq!(Some(TokenWrapper { col: 0, line: 1, token: String(b"a") }), lexer.next().map(Result::unwrap));
+        assert_eq!(Span { start: 0, end: 1 }, lexer.span());
+        let err = lexer.next().unwrap().unwrap_err();
+        assert_eq!((Span { start: 2, end: 5 }, ErrorKind::UnterminatedString), (err.span, err.kind));
+        assert!(lexer.next().is_none());
+
+        let mut lexer = Lexer::new(b"");
+        assert_eq!(Some(TokenWrapper { col: 0, line: 1, token: Eof }), lexer.next().map(Result::unwrap));
+        assert!(lexer.next().is_none());
+    }
+
+    fn do_test(input: &[u8]) -> Vec<TokenWrapper<'_>> {
+        let v = Lexer::new(input).collect::<Result<Vec<_>, _>>().unwrap();
         println!("tokens: {}", v.len());
         println!("vec![");
         for x in &v[0..min(20, v.len())] {
@@ -1,4 +1,35 @@
+mod error;
 mod lexer;
 mod tokens;
 pub mod parser;
+pub mod berry;
+pub mod lockfile;
+pub mod stringify;
+pub mod format;
+pub mod semver;
+pub mod merge;
+pub mod cst;
+pub mod edit;
+pub mod descriptor;
+pub mod resolution;
+mod digest;
+pub mod integrity;
+pub mod graph;
+pub mod manifest;
+pub mod why;
+pub mod dedupe;
+pub mod diff;
+pub mod report;
+pub mod npm;
+mod json;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
+
+pub use format::{detect_format, load};
+#[cfg(feature = "serde")]
+pub use de::from_slice;
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_writer};
 
@@ -0,0 +1,551 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use std::rc::Rc;
+
+use crate::berry;
+use crate::descriptor::Descriptor;
+use crate::format::Format;
+use crate::integrity;
+use crate::integrity::Integrity;
+use crate::npm;
+use crate::parser;
+use crate::parser::Value;
+use crate::resolution::Resolution;
+use crate::semver::Version;
+use crate::stringify::stringify;
+
+/// A typed view of a yarn lockfile.
+///
+/// Every descriptor key (e.g. `"@babel/core@^7.12.3"`) maps to its own [`Entry`]. Keys that
+/// share an entry in the file, such as `"a@^1.0.0", "a@^1.1.0":`, map to equal entries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Lockfile {
+    /// The `__metadata` block, only present in Berry (v2+) lockfiles.
+    pub metadata: Option<Metadata>,
+    /// The entries, keyed by descriptor.
+    pub entries: BTreeMap<String, Entry>,
+}
+
+/// The `__metadata` block of a Berry lockfile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The lockfile format version.
+    pub version: u32,
+    /// The cache key, e.g. `10c0`.
+    pub cache_key: Option<String>,
+}
+
+/// A single resolved package in the lockfile.
+///
+/// Field names follow the keys yarn writes, see [https://github.com/yarnpkg/yarn/blob/master/src/lockfile/index.js](https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/src/lockfile/index.js#L313)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entry {
+    /// The package name, only written by yarn when it differs from the descriptor name.
+    pub name: Option<String>,
+    /// The resolved version.
+    pub version: String,
+    /// The unique id, only written by yarn when it differs from the version.
+    pub uid: Option<String>,
+    /// The URL the package was fetched from.
+    pub resolved: Option<String>,
+    /// The subresource integrity of the package tarball.
+    pub integrity: Option<String>,
+    /// The registry the package was resolved from, e.g. `npm`.
+    pub registry: Option<String>,
+    /// The dependencies, as package name to range.
+    pub dependencies: BTreeMap<String, String>,
+    /// The optional dependencies, as package name to range.
+    pub optional_dependencies: BTreeMap<String, String>,
+    /// The permissions granted to the package.
+    pub permissions: BTreeMap<String, bool>,
+    /// The prebuilt variants of the package, as variant name to hash.
+    pub prebuilt_variants: BTreeMap<String, String>,
+    /// The locator the package was resolved to, Berry only.
+    pub resolution: Option<String>,
+    /// The checksum of the package archive, Berry only.
+    pub checksum: Option<String>,
+    /// The language of the package, e.g. `node`, Berry only.
+    pub language_name: Option<String>,
+    /// The link type of the package, `hard` or `soft`, Berry only.
+    pub link_type: Option<String>,
+    /// The platform conditions, e.g. `os=darwin`, Berry only.
+    pub conditions: Option<String>,
+    /// The peer dependencies, as package name to range, Berry only.
+    pub peer_dependencies: BTreeMap<String, String>,
+    /// The binaries, as command name to path, Berry only.
+    pub bin: BTreeMap<String, String>,
+    /// Any other field not known by this crate.
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Lockfile error.
+///
+/// This error is returned when the input is not a valid lockfile, either because it cannot be
+/// parsed or because an entry does not have the expected shape.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The input could not be parsed.
+    Parse(parser::Error),
+    /// The value of a top-level key is not an object.
+    InvalidEntry {
+        /// The descriptor key of the entry.
+        key: String,
+    },
+    /// A required field is missing from an entry.
+    MissingField {
+        /// The descriptor key of the entry.
+        key: String,
+        /// The name of the missing field.
+        field: &'static str,
+    },
+    /// A field of an entry has the wrong shape.
+    InvalidField {
+        /// The descriptor key of the entry.
+        key: String,
+        /// The name of the field, nested fields are joined by `.`.
+        field: String,
+        /// A description of the expected shape.
+        expected: &'static str,
+    },
+    /// The lockfile format could not be detected.
+    UnknownFormat,
+    /// The lockfile format was detected, but is not supported.
+    UnsupportedFormat(Format),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::InvalidEntry { key } => write!(f, "Lockfile error[{key}]: expected an object"),
+            Self::MissingField { key, field } => write!(f, "Lockfile error[{key}]: missing field `{field}`"),
+            Self::InvalidField { key, field, expected } => write!(f, "Lockfile error[{key}]: field `{field}` expected {expected}"),
+            Self::UnknownFormat => write!(f, "Lockfile error: unknown lockfile format"),
+            Self::UnsupportedFormat(format) => write!(f, "Lockfile error: unsupported lockfile format {format}"),
+        }
+    }
+}
+
+impl From<parser::Error> for Error {
+    fn from(e: parser::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl Lockfile {
+    /// Parse the input yarn lock data into a typed lockfile.
+    ///
+    /// # Errors
+    /// - [`Error::Parse`]: When parsing failed
+    /// - Any other [`Error`]: When an entry does not have the expected shape
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        Self::from_value(&parser::parse(input)?)
+    }
+
+    /// Parse the input Yarn Berry (v2+) lock data into a typed lockfile.
+    ///
+    /// # Errors
+    /// - [`Error::Parse`]: When parsing failed
+    /// - Any other [`Error`]: When an entry does not have the expected shape
+    pub fn parse_berry(input: &[u8]) -> Result<Self, Error> {
+        Self::from_value(&berry::parse(input)?)
+    }
+
+    /// Parse the input npm `package-lock.json` into a typed lockfile, see [`npm::parse`].
+    ///
+    /// # Errors
+    /// - [`Error::Parse`]: When the input is not valid JSON
+    /// - Any other [`Error`]: When a package does not have the expected shape
+    pub fn parse_npm(input: &[u8]) -> Result<Self, Error> {
+        Self::from_value(&npm::parse(input)?)
+    }
+
+    /// Build a typed lockfile from the value returned by [`parser::parse`], [`berry::parse`] or
+    /// [`npm::parse`].
+    ///
+    /// # Errors
+    /// - [`Error`]: When an entry does not have the expected shape
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let Value::Object(map) = value else {
+            return Err(Error::InvalidEntry { key: String::new() });
+        };
+        let mut metadata = None;
+        let mut entries = BTreeMap::new();
+        for (key, value) in map {
+            if key == berry::METADATA_KEY {
+                metadata = Some(Metadata::from_value(value)?);
+            } else {
+                entries.insert(key.clone(), Entry::from_value(key, value)?);
+            }
+        }
+        Ok(Self { metadata, entries })
+    }
+
+    /// Get the entry a descriptor resolves to.
+    #[must_use]
+    pub fn get(&self, descriptor: &str) -> Option<&Entry> {
+        self.entries.get(descriptor)
+    }
+
+    /// Iterate over the descriptors and their entries, ordered by descriptor.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Entry)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// The number of descriptors in the lockfile.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the lockfile has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the descriptors whose semver range is not satisfied by the version of their
+    /// entry, e.g. a hand-edited `foo@^2.0.0` resolved to `1.9.0`.
+    ///
+    /// Descriptors without a semver range, see [`Descriptor::semver_range`], and entries without a
+    /// valid version are skipped.
+    pub fn unsatisfied(&self) -> impl Iterator<Item = (&str, &Entry)> {
+        self.iter().filter(|(descriptor, entry)| {
+            let (Some(range), Ok(version)) = (Descriptor::parse(descriptor).semver_range(), Version::parse(&entry.version)) else {
+                return false;
+            };
+            !range.satisfies(&version)
+        })
+    }
+
+    /// Convert the lockfile back into the value shape returned by [`parser::parse`].
+    ///
+    /// The Berry `__metadata` block is not included.
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        Value::Object(self.entries.iter().map(|(k, v)| (k.clone(), v.to_value())).collect())
+    }
+}
+
+/// Writes the lockfile in the yarn v1 format, see [`stringify`].
+///
+/// The Berry `__metadata` block is not written.
+impl fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&stringify(&self.to_value()))
+    }
+}
+
+impl Metadata {
+    fn from_value(value: &Value) -> Result<Self, Error> {
+        let key = berry::METADATA_KEY;
+        let map = object_field(key, key, value)?;
+        let version = map.get("version").ok_or_else(|| Error::MissingField { key: key.to_string(), field: "version" })?;
+        let version = string_field(key, "version", version)?
+            .parse()
+            .map_err(|_| Error::InvalidField { key: key.to_string(), field: "version".to_string(), expected: "an integer" })?;
+        let cache_key = map.get("cacheKey").map(|v| string_field(key, "cacheKey", v)).transpose()?;
+        Ok(Self { version, cache_key })
+    }
+
+    /// Convert the metadata back into the value shape returned by [`berry::parse`].
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let mut map = HashMap::new();
+        map.insert("version".to_string(), string_value(&self.version.to_string()));
+        if let Some(cache_key) = &self.cache_key {
+            map.insert("cacheKey".to_string(), string_value(cache_key));
+        }
+        Value::Object(map)
+    }
+}
+
+impl Entry {
+    fn from_value(key: &str, value: &Value) -> Result<Self, Error> {
+        let Value::Object(map) = value else {
+            return Err(Error::InvalidEntry { key: key.to_string() });
+        };
+        let mut entry = Self::default();
+        let mut version = None;
+        for (field, value) in map {
+            match field.as_str() {
+                "name" => entry.name = Some(string_field(key, field, value)?),
+                "version" => version = Some(string_field(key, field, value)?),
+                "uid" => entry.uid = Some(string_field(key, field, value)?),
+                "resolved" => entry.resolved = Some(string_field(key, field, value)?),
+                "integrity" => entry.integrity = Some(string_field(key, field, value)?),
+                "registry" => entry.registry = Some(string_field(key, field, value)?),
+                "dependencies" => entry.dependencies = string_map_field(key, field, value)?,
+                "optionalDependencies" => entry.optional_dependencies = string_map_field(key, field, value)?,
+                "permissions" => entry.permissions = bool_map_field(key, field, value)?,
+                "prebuiltVariants" => entry.prebuilt_variants = string_map_field(key, field, value)?,
+                "resolution" => entry.resolution = Some(string_field(key, field, value)?),
+                "checksum" => entry.checksum = Some(string_field(key, field, value)?),
+                "languageName" => entry.language_name = Some(string_field(key, field, value)?),
+                "linkType" => entry.link_type = Some(string_field(key, field, value)?),
+                "conditions" => entry.conditions = Some(string_field(key, field, value)?),
+                "peerDependencies" => entry.peer_dependencies = string_map_field(key, field, value)?,
+                "bin" => entry.bin = string_map_field(key, field, value)?,
+                _ => {
+                    entry.extra.insert(field.clone(), value.clone());
+                }
+            }
+        }
+        entry.version = version.ok_or_else(|| Error::MissingField { key: key.to_string(), field: "version" })?;
+        Ok(entry)
+    }
+
+    /// Parse the `resolved` field, see [`Resolution::parse`].
+    #[must_use]
+    pub fn resolution(&self) -> Option<Resolution> {
+        self.resolved.as_deref().and_then(Resolution::parse)
+    }
+
+    /// Parse the `integrity` field, or the legacy sha1 fragment of the `resolved` field when the
+    /// entry has no `integrity`.
+    ///
+    /// # Errors
+    /// - [`integrity::Error`]: When the `integrity` field is invalid
+    pub fn subresource_integrity(&self) -> Result<Option<Integrity>, integrity::Error> {
+        if let Some(integrity) = &self.integrity {
+            return Integrity::parse(integrity).map(Some);
+        }
+        match self.resolution() {
+            Some(Resolution::Registry { sha1: Some(sha1), .. } | Resolution::Tarball { sha1: Some(sha1), .. }) => Integrity::from_sha1_hex(&sha1).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Convert the entry back into the value shape returned by [`parser::parse`].
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let mut map = HashMap::new();
+        map.insert("version".to_string(), string_value(&self.version));
+        let mut string = |field: &str, value: &Option<String>| {
+            if let Some(s) = value {
+                map.insert(field.to_string(), string_value(s));
+            }
+        };
+        string("name", &self.name);
+        string("uid", &self.uid);
+        string("resolved", &self.resolved);
+        string("integrity", &self.integrity);
+        string("registry", &self.registry);
+        string("resolution", &self.resolution);
+        string("checksum", &self.checksum);
+        string("languageName", &self.language_name);
+        string("linkType", &self.link_type);
+        string("conditions", &self.conditions);
+        let mut object = |field: &str, value: Option<Value>| {
+            if let Some(v) = value {
+                map.insert(field.to_string(), v);
+            }
+        };
+        object("dependencies", string_map_value(&self.dependencies));
+        object("optionalDependencies", string_map_value(&self.optional_dependencies));
+        object("prebuiltVariants", string_map_value(&self.prebuilt_variants));
+        object("peerDependencies", string_map_value(&self.peer_dependencies));
+        object("bin", string_map_value(&self.bin));
+        object("permissions", (!self.permissions.is_empty()).then(|| {
+            Value::Object(self.permissions.iter().map(|(k, v)| (k.clone(), Value::Boolean(*v))).collect())
+        }));
+        for (k, v) in &self.extra {
+            map.insert(k.clone(), v.clone());
+        }
+        Value::Object(map)
+    }
+}
+
+fn string_value(s: &str) -> Value {
+    Value::String(Rc::new(s.to_string()))
+}
+
+fn string_map_value(map: &BTreeMap<String, String>) -> Option<Value> {
+    (!map.is_empty()).then(|| Value::Object(map.iter().map(|(k, v)| (k.clone(), string_value(v))).collect()))
+}
+
+fn string_field(key: &str, field: &str, value: &Value) -> Result<String, Error> {
+    match value {
+        Value::String(s) => Ok(s.to_string()),
+        _ => Err(Error::InvalidField { key: key.to_string(), field: field.to_string(), expected: "a string" }),
+    }
+}
+
+fn object_field<'v>(key: &str, field: &str, value: &'v Value) -> Result<&'v HashMap<String, Value>, Error> {
+    match value {
+        Value::Object(map) => Ok(map),
+        _ => Err(Error::InvalidField { key: key.to_string(), field: field.to_string(), expected: "an object" }),
+    }
+}
+
+fn string_map_field(key: &str, field: &str, value: &Value) -> Result<BTreeMap<String, String>, Error> {
+    object_field(key, field, value)?
+        .iter()
+        .map(|(k, v)| match v {
+            Value::String(s) => Ok((k.clone(), s.to_string())),
+            _ => Err(Error::InvalidField { key: key.to_string(), field: format!("{field}.{k}"), expected: "a string" }),
+        })
+        .collect()
+}
+
+fn bool_map_field(key: &str, field: &str, value: &Value) -> Result<BTreeMap<String, bool>, Error> {
+    object_field(key, field, value)?
+        .iter()
+        .map(|(k, v)| match v {
+            Value::Boolean(b) => Ok((k.clone(), *b)),
+            _ => Err(Error::InvalidField { key: key.to_string(), field: format!("{field}.{k}"), expected: "a boolean" }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entry() {
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        let entry = lockfile.get("@colors/colors@1.5.0").unwrap();
+        assert_eq!("1.5.0", entry.version);
+        assert_eq!(Some("https://registry.yarnpkg.com/@colors/colors/-/colors-1.5.0.tgz#bb504579c1cae923e6576a4f5da43d25f97bdbd9"), entry.resolved.as_deref());
+        assert_eq!(Some("sha512-ooWCrlZP11i8GImSjTHYHLkvFDP48nS4+204nGb1RiX/WXYHmJA2III9/e2DWVabCESdW7hBAEzHRqUn9OUVvQ=="), entry.integrity.as_deref());
+    }
+
+    #[test]
+    fn parse_multiple_keys() {
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.2")).unwrap();
+        let a = lockfile.get("@babel/code-frame@^7.0.0").unwrap();
+        let b = lockfile.get("@babel/code-frame@^7.21.4").unwrap();
+        assert_eq!(a, b);
+        assert_eq!("7.21.4", a.version);
+        assert_eq!(Some("^7.18.6"), a.dependencies.get("@babel/highlight").map(String::as_str));
+    }
+
+    #[test]
+    fn parse_all_fields() {
+        let input = br#"
+"a@^1.0.0":
+  name b
+  version "1.0.0"
+  uid "1.0.0-uid"
+  registry npm
+  optionalDependencies:
+    c "^2.0.0"
+  permissions:
+    scripts true
+  prebuiltVariants:
+    node-87-linux-x64 abc
+  custom "x"
+"#;
+        let lockfile = Lockfile::parse(input).unwrap();
+        let entry = lockfile.get("a@^1.0.0").unwrap();
+        assert_eq!(Some("b"), entry.name.as_deref());
+        assert_eq!(Some("1.0.0-uid"), entry.uid.as_deref());
+        assert_eq!(Some("npm"), entry.registry.as_deref());
+        assert_eq!(Some(&"^2.0.0".to_string()), entry.optional_dependencies.get("c"));
+        assert_eq!(Some(&true), entry.permissions.get("scripts"));
+        assert_eq!(Some(&"abc".to_string()), entry.prebuilt_variants.get("node-87-linux-x64"));
+        assert!(matches!(entry.extra.get("custom"), Some(Value::String(s)) if s.as_str() == "x"));
+    }
+
+    #[test]
+    fn parse_resolutions() {
+        for input in [&include_bytes!("test.lock.1")[..], include_bytes!("test.lock.2")] {
+            for (key, entry) in Lockfile::parse(input).unwrap().iter() {
+                assert!(entry.resolution().is_some(), "{key}");
+            }
+        }
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.2")).unwrap();
+        let entry = lockfile.iter().find(|(key, _)| key.starts_with("react-data-grid@")).unwrap().1;
+        assert!(matches!(entry.resolution(), Some(Resolution::Git { repo, .. }) if repo == "pgadmin-org/react-data-grid"));
+    }
+
+    #[test]
+    fn parse_subresource_integrity() {
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        for (key, entry) in lockfile.iter() {
+            let integrity = entry.subresource_integrity().unwrap().unwrap();
+            assert_eq!(entry.integrity.as_deref().unwrap(), integrity.to_string(), "{key}");
+        }
+        let mut entry = lockfile.iter().next().unwrap().1.clone();
+        entry.integrity = None;
+        entry.resolved = Some("https://registry.yarnpkg.com/a/-/a-1.0.0.tgz#aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".to_string());
+        assert!(entry.subresource_integrity().unwrap().unwrap().verify(b"hello"));
+        entry.resolved = Some("https://registry.yarnpkg.com/a/-/a-1.0.0.tgz".to_string());
+        assert_eq!(Ok(None), entry.subresource_integrity());
+        entry.integrity = Some("sha512-invalid".to_string());
+        assert!(entry.subresource_integrity().is_err());
+    }
+
+    #[test]
+    fn unsatisfied_descriptors() {
+        for input in [&include_bytes!("test.lock.1")[..], include_bytes!("test.lock.2")] {
+            assert_eq!(0, Lockfile::parse(input).unwrap().unsatisfied().count());
+        }
+        assert_eq!(0, Lockfile::parse_berry(include_bytes!("test.berry.lock")).unwrap().unsatisfied().count());
+
+        let mut lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        let (key, entry) = lockfile.iter().find(|(key, _)| key.contains('^')).map(|(k, e)| (k.to_string(), e.clone())).unwrap();
+        let descriptor = Descriptor::parse(&key);
+        lockfile.entries.insert(format!("{}@^999.0.0", descriptor.name), entry);
+        let unsatisfied: Vec<_> = lockfile.unsatisfied().map(|(key, _)| key).collect();
+        assert_eq!(vec![format!("{}@^999.0.0", descriptor.name)], unsatisfied);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = include_str!("test.lock.2");
+        let lockfile = Lockfile::parse(input.as_bytes()).unwrap();
+        assert_eq!(input, lockfile.to_string());
+    }
+
+    #[test]
+    fn parse_berry() {
+        let lockfile = Lockfile::parse_berry(include_bytes!("test.berry.lock")).unwrap();
+        assert_eq!(Some(Metadata { version: 8, cache_key: Some("10c0".to_string()) }), lockfile.metadata);
+        let entry = lockfile.get("@babel/code-frame@npm:^7.24.7").unwrap();
+        assert_eq!("7.24.7", entry.version);
+        assert_eq!(Some("@babel/code-frame@npm:7.24.7"), entry.resolution.as_deref());
+        assert_eq!(Some("node"), entry.language_name.as_deref());
+        assert_eq!(Some("hard"), entry.link_type.as_deref());
+        assert!(entry.checksum.as_deref().unwrap().starts_with("10c0/ab0af539"));
+        assert_eq!(Some(&"npm:^1.0.0".to_string()), entry.dependencies.get("picocolors"));
+        let entry = lockfile.get("typescript@npm:^5.0.0").unwrap();
+        assert_eq!(Some(&"bin/tsc".to_string()), entry.bin.get("tsc"));
+        let entry = lockfile.get("fsevents@npm:~2.3.2").unwrap();
+        assert_eq!(Some("os=darwin"), entry.conditions.as_deref());
+        assert!(lockfile.get("my-app@workspace:.").unwrap().extra.contains_key("dependenciesMeta"));
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        let err = Lockfile::parse_berry(b"__metadata:\n  version: x\n").unwrap_err();
+        assert!(matches!(err, Error::InvalidField { ref key, expected: "an integer", .. } if key == "__metadata"));
+    }
+
+    #[test]
+    fn missing_version() {
+        let err = Lockfile::parse(b"a@1:\n  resolved \"x\"\n").unwrap_err();
+        assert!(matches!(err, Error::MissingField { ref key, field: "version" } if key == "a@1"));
+    }
+
+    #[test]
+    fn invalid_field() {
+        let err = Lockfile::parse(b"a@1:\n  version \"1\"\n  dependencies:\n    b true\n").unwrap_err();
+        assert!(matches!(err, Error::InvalidField { ref field, expected: "a string", .. } if field == "dependencies.b"));
+        let err = Lockfile::parse(b"a@1 \"1\"\n").unwrap_err();
+        assert!(matches!(err, Error::InvalidEntry { ref key } if key == "a@1"));
+    }
+}
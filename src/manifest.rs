@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::json::Json;
+
+/// The dependency fields of a `package.json`, the roots of a lockfile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// The `dependencies`, as package name to range.
+    pub dependencies: BTreeMap<String, String>,
+    /// The `devDependencies`, as package name to range.
+    pub dev_dependencies: BTreeMap<String, String>,
+    /// The `optionalDependencies`, as package name to range.
+    pub optional_dependencies: BTreeMap<String, String>,
+    /// The `peerDependencies`, as package name to range.
+    pub peer_dependencies: BTreeMap<String, String>,
+}
+
+/// Manifest error.
+///
+/// This error is returned when the input is not a valid `package.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The input is not valid JSON.
+    InvalidJson {
+        /// The byte offset of the error.
+        offset: usize,
+    },
+    /// A field does not have the expected shape.
+    InvalidField {
+        /// The name of the field, nested fields are joined by `.`.
+        field: String,
+        /// A description of the expected shape.
+        expected: &'static str,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidJson { offset } => write!(f, "Manifest error: invalid JSON at byte {offset}"),
+            Self::InvalidField { field, expected } => write!(f, "Manifest error: field `{field}` expected {expected}"),
+        }
+    }
+}
+
+impl Manifest {
+    /// Parse a `package.json`, other fields than the dependencies, the name and the version are
+    /// ignored.
+    ///
+    /// # Errors
+    /// - [`Error::InvalidJson`]: When the input is not valid JSON
+    /// - [`Error::InvalidField`]: When a known field does not have the expected shape
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let json = Json::parse(input).map_err(|offset| Error::InvalidJson { offset })?;
+        if !matches!(json, Json::Object(_)) {
+            return Err(Error::InvalidField { field: String::new(), expected: "an object" });
+        }
+        let string = |field: &str| match json.get(field) {
+            None | Some(Json::Null) => Ok(None),
+            Some(Json::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(Error::InvalidField { field: field.to_string(), expected: "a string" }),
+        };
+        let map = |field: &str| {
+            let mut map = BTreeMap::new();
+            match json.get(field) {
+                None | Some(Json::Null) => {}
+                Some(Json::Object(members)) => {
+                    for (name, range) in members {
+                        let range = range.as_str().ok_or_else(|| Error::InvalidField { field: format!("{field}.{name}"), expected: "a string" })?;
+                        map.insert(name.clone(), range.to_string());
+                    }
+                }
+                Some(_) => return Err(Error::InvalidField { field: field.to_string(), expected: "an object" }),
+            }
+            Ok(map)
+        };
+        Ok(Self {
+            name: string("name")?,
+            version: string("version")?,
+            dependencies: map("dependencies")?,
+            dev_dependencies: map("devDependencies")?,
+            optional_dependencies: map("optionalDependencies")?,
+            peer_dependencies: map("peerDependencies")?,
+        })
+    }
+
+    /// Iterate over the dependencies installed by the package manager, `dependencies`,
+    /// `devDependencies` and `optionalDependencies`, as package name and range.
+    ///
+    /// A package in `optionalDependencies` is skipped in `dependencies`, like npm and yarn do.
+    pub fn installed_dependencies(&self) -> impl Iterator<Item = (&str, &str)> {
+        let dependencies = self.dependencies.iter().filter(|(name, _)| !self.optional_dependencies.contains_key(*name));
+        dependencies.chain(&self.dev_dependencies).chain(&self.optional_dependencies).map(|(name, range)| (name.as_str(), range.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest() {
+        let input = br#"{
+  "name": "app",
+  "private": true,
+  "scripts": { "build": "tsc" },
+  "dependencies": { "react": "^18.0.0", "fsevents": "^2.3.0" },
+  "devDependencies": { "typescript": "~5.4.0" },
+  "optionalDependencies": { "fsevents": "^2.3.2" },
+  "peerDependencies": null
+}"#;
+        let manifest = Manifest::parse(input).unwrap();
+        assert_eq!((Some("app"), None), (manifest.name.as_deref(), manifest.version.as_deref()));
+        assert!(manifest.peer_dependencies.is_empty());
+        let installed: Vec<_> = manifest.installed_dependencies().collect();
+        assert_eq!(vec![("react", "^18.0.0"), ("typescript", "~5.4.0"), ("fsevents", "^2.3.2")], installed);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Err(Error::InvalidJson { offset: 1 }), Manifest::parse(b"{,}"));
+        assert_eq!(Err(Error::InvalidField { field: String::new(), expected: "an object" }), Manifest::parse(b"[]"));
+        assert_eq!(Err(Error::InvalidField { field: "name".to_string(), expected: "a string" }), Manifest::parse(b"{\"name\": 1}"));
+        let error = Manifest::parse(b"{\"dependencies\": {\"a\": true}}").unwrap_err();
+        assert_eq!("Manifest error: field `dependencies.a` expected a string", error.to_string());
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::descriptor::Descriptor;
+use crate::lockfile::{Entry, Lockfile};
+use crate::parser::Value;
+use crate::semver::Version;
+use crate::stringify::stringify_without_header;
+
+/// A descriptor both sides resolved to incompatible versions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The descriptor, e.g. `lodash@^4.17.0`.
+    pub descriptor: String,
+    /// The entry on our side.
+    pub ours: Entry,
+    /// The entry on their side.
+    pub theirs: Entry,
+}
+
+/// The result of [`merge`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeResult {
+    /// The merged lockfile, without the conflicting descriptors.
+    pub lockfile: Lockfile,
+    /// The descriptors that could not be merged, ordered by descriptor.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// Returns `true` if the merge has no conflicts.
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Writes the merged lockfile in the yarn v1 format, followed by the conflicting descriptors
+/// between git-style conflict markers.
+///
+/// The output can be read back with [`crate::parser::parse_with_conflicts`].
+impl fmt::Display for MergeResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lockfile)?;
+        for conflict in &self.conflicts {
+            let side = |entry: &Entry| stringify_without_header(&Value::Object(HashMap::from([(conflict.descriptor.clone(), entry.to_value())])));
+            if !self.lockfile.is_empty() {
+                f.write_str("\n")?;
+            }
+            write!(f, "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n", side(&conflict.ours), side(&conflict.theirs))?;
+        }
+        Ok(())
+    }
+}
+
+/// Three-way merge lockfiles by descriptor.
+///
+/// Each descriptor is merged on its own: a side that did not change it from `base` takes the
+/// other side, including deletions, and a modification wins over a deletion. When both sides
+/// changed the same descriptor, the higher version is taken if both versions satisfy the
+/// descriptor's range (e.g. `1.2.0` and `1.4.1` for `^1.0.0`), otherwise the descriptor is a
+/// [`Conflict`].
+#[must_use]
+pub fn merge(base: &Lockfile, ours: &Lockfile, theirs: &Lockfile) -> MergeResult {
+    let mut entries = BTreeMap::new();
+    let mut conflicts = vec![];
+    let descriptors: BTreeSet<&String> = ours.entries.keys().chain(theirs.entries.keys()).collect();
+    for descriptor in descriptors {
+        let (b, o, t) = (base.get(descriptor), ours.get(descriptor), theirs.get(descriptor));
+        let merged = if o == t || t == b {
+            o
+        } else if o == b {
+            t
+        } else if let (Some(o), Some(t)) = (o, t) {
+            let entry = newer_compatible(descriptor, o, t);
+            if entry.is_none() {
+                conflicts.push(Conflict { descriptor: descriptor.clone(), ours: o.clone(), theirs: t.clone() });
+            }
+            entry
+        } else {
+            // one side deleted the descriptor, the other modified it
+            o.or(t)
+        };
+        if let Some(entry) = merged {
+            entries.insert(descriptor.clone(), entry.clone());
+        }
+    }
+    MergeResult { lockfile: Lockfile { metadata: ours.metadata.clone(), entries }, conflicts }
+}
+
+/// Pick the entry with the higher version, preferring ours on a tie, if both versions satisfy
+/// the semver range of `descriptor`.
+fn newer_compatible<'e>(descriptor: &str, ours: &'e Entry, theirs: &'e Entry) -> Option<&'e Entry> {
+    let range = Descriptor::parse(descriptor).semver_range()?;
+    let (Ok(o), Ok(t)) = (Version::parse(&ours.version), Version::parse(&theirs.version)) else {
+        return None;
+    };
+    (range.satisfies(&o) && range.satisfies(&t)).then_some(if t > o { theirs } else { ours })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_with_conflicts, ParseResultType};
+
+    fn lockfile(entries: &[(&str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::default();
+        for (descriptor, version) in entries {
+            lockfile.entries.insert((*descriptor).to_string(), Entry { version: (*version).to_string(), ..Entry::default() });
+        }
+        lockfile
+    }
+
+    #[test]
+    fn merge_unions_descriptors() {
+        let base = lockfile(&[("a@^1.0.0", "1.0.0"), ("b@^1.0.0", "1.0.0"), ("c@^1.0.0", "1.0.0")]);
+        let ours = lockfile(&[("a@^1.0.0", "1.1.0"), ("b@^1.0.0", "1.0.0"), ("d@^1.0.0", "1.0.0")]);
+        let theirs = lockfile(&[("a@^1.0.0", "1.0.0"), ("c@^1.0.0", "1.0.0"), ("e@^1.0.0", "1.0.0")]);
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(lockfile(&[("a@^1.0.0", "1.1.0"), ("d@^1.0.0", "1.0.0"), ("e@^1.0.0", "1.0.0")]), result.lockfile);
+    }
+
+    #[test]
+    fn merge_takes_higher_compatible_version() {
+        let base = lockfile(&[("a@^1.0.0", "1.0.0"), ("b@^0.2.0", "0.2.0")]);
+        let ours = lockfile(&[("a@^1.0.0", "1.4.1"), ("b@^0.2.0", "0.2.3")]);
+        let theirs = lockfile(&[("a@^1.0.0", "1.2.0"), ("b@^0.2.0", "0.2.5")]);
+        let result = merge(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(lockfile(&[("a@^1.0.0", "1.4.1"), ("b@^0.2.0", "0.2.5")]), result.lockfile);
+    }
+
+    #[test]
+    fn merge_checks_descriptor_range() {
+        let base = lockfile(&[("a@~1.2.0", "1.2.0"), ("b@~1.2.0", "1.2.0")]);
+        let ours = lockfile(&[("a@~1.2.0", "1.2.1"), ("b@~1.2.0", "1.2.1")]);
+        let theirs = lockfile(&[("a@~1.2.0", "1.3.0"), ("b@~1.2.0", "1.2.4")]);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(lockfile(&[("b@~1.2.0", "1.2.4")]), result.lockfile);
+        assert_eq!(1, result.conflicts.len());
+        assert_eq!(("a@~1.2.0", "1.2.1", "1.3.0"), (result.conflicts[0].descriptor.as_str(), result.conflicts[0].ours.version.as_str(), result.conflicts[0].theirs.version.as_str()));
+    }
+
+    #[test]
+    fn merge_keeps_modification_over_deletion() {
+        let base = lockfile(&[("a@^1.0.0", "1.0.0")]);
+        let ours = lockfile(&[]);
+        let theirs = lockfile(&[("a@^1.0.0", "1.1.0")]);
+        assert_eq!(theirs, merge(&base, &ours, &theirs).lockfile);
+    }
+
+    #[test]
+    fn merge_reports_incompatible_versions() {
+        let base = lockfile(&[("a@latest", "1.0.0"), ("b@^1.0.0", "1.0.0")]);
+        let ours = lockfile(&[("a@latest", "2.0.0"), ("b@^1.0.0", "1.0.0")]);
+        let theirs = lockfile(&[("a@latest", "3.0.0"), ("b@^1.0.0", "1.0.0")]);
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(lockfile(&[("b@^1.0.0", "1.0.0")]), result.lockfile);
+        assert_eq!(1, result.conflicts.len());
+        assert_eq!(("a@latest", "2.0.0", "3.0.0"), (result.conflicts[0].descriptor.as_str(), result.conflicts[0].ours.version.as_str(), result.conflicts[0].theirs.version.as_str()));
+
+        let output = result.to_string();
+        assert!(output.ends_with("\n<<<<<<< ours\na@latest:\n  version \"2.0.0\"\n=======\na@latest:\n  version \"3.0.0\"\n>>>>>>> theirs\n"), "{output}");
+        let parsed = parse_with_conflicts(output.as_bytes()).unwrap();
+        assert_eq!(ParseResultType::Merge, parsed.kind);
+        assert_eq!(theirs.to_value(), parsed.object);
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::{btree_map, BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::descriptor::Descriptor;
+use crate::error::ErrorKind;
+use crate::graph::DependencyGraph;
+use crate::json::Json;
+use crate::lockfile;
+use crate::lockfile::{Entry, Lockfile};
+use crate::manifest::Manifest;
+use crate::parser;
+use crate::parser::Value;
+use crate::resolution::Resolution;
+use crate::semver::Version;
+use crate::tokens::Span;
+
+/// npm error.
+///
+/// This error is returned when a lockfile cannot be converted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A dependency does not resolve to an entry of the lockfile.
+    Unresolved {
+        /// The first descriptor of the package that has the dependency, or `package.json`.
+        dependent: String,
+        /// The dependency as a descriptor, e.g. `foo@^1.0.0`.
+        dependency: String,
+    },
+    /// A dependency would have to be nested in a copy of itself, the cycle cannot be laid out in
+    /// `node_modules`.
+    Cycle {
+        /// The first descriptor of the package that has the dependency.
+        dependent: String,
+        /// The dependency as a descriptor, e.g. `foo@^1.0.0`.
+        dependency: String,
+    },
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unresolved { dependent, dependency } => write!(f, "npm error[{dependent}]: unresolved dependency `{dependency}`"),
+            Self::Cycle { dependent, dependency } => write!(f, "npm error[{dependent}]: dependency `{dependency}` would be nested in a copy of itself"),
+        }
+    }
+}
+
+/// A dependency edge between two install locations.
+struct Link {
+    from: String,
+    to: String,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Prod,
+    Dev,
+    Optional,
+}
+
+/// The dependencies of an install location, as package name, range, graph node and kind.
+type Dependencies<'a> = Vec<(&'a str, &'a str, usize, Kind)>;
+
+/// The `dev`, `optional` and `devOptional` flags of an install location, set when the package is
+/// only needed by dev dependencies, optional dependencies, or either.
+#[derive(Clone, Copy)]
+struct Flags {
+    dev: bool,
+    optional: bool,
+    dev_optional: bool,
+}
+
+/// Convert a yarn lockfile and the root `package.json` to an npm `package-lock.json` with
+/// `lockfileVersion` 3.
+///
+/// Packages are placed in `node_modules` like npm does: breadth-first from the root, every
+/// dependency goes to the highest directory where it neither conflicts with another version of
+/// the same name nor shadows that version for a package already placed below. A dependency that
+/// already resolves to the right version from the dependent is linked to it, which ends most
+/// cycles. Packages not reachable from the manifest are left out. `resolved` drops the sha1 fragment of tarball URLs,
+/// and a missing `integrity` falls back to that sha1.
+///
+/// # Errors
+/// - [`Error::Unresolved`]: When a dependency of a reachable package is not in the lockfile
+/// - [`Error::Cycle`]: When a dependency cycle alternates between versions so that a package
+///   would be nested in a copy of itself
+pub fn to_package_lock(lockfile: &Lockfile, manifest: &Manifest) -> Result<String, Error> {
+    let graph = DependencyGraph::new(lockfile);
+    let mut tree: BTreeMap<String, usize> = BTreeMap::new();
+    let mut links: Vec<Link> = vec![];
+    let mut queue: VecDeque<(String, Dependencies)> = VecDeque::new();
+
+    let mut root_dependencies = vec![];
+    for (name, range, kind) in manifest_dependencies(manifest) {
+        let id = graph.resolve(name, range).ok_or_else(|| Error::Unresolved { dependent: "package.json".to_string(), dependency: format!("{name}@{range}") })?;
+        root_dependencies.push((name, range, id, kind));
+    }
+    queue.push_back((String::new(), root_dependencies));
+
+    while let Some((dependent, dependencies)) = queue.pop_front() {
+        for (name, range, id, kind) in dependencies {
+            if let Some(location) = lookup(&tree, &dependent, name).filter(|location| tree[location] == id) {
+                links.push(Link { from: dependent.clone(), to: location, kind });
+                continue;
+            }
+            let mut target = dependent.clone();
+            for dir in ancestors(&dependent) {
+                if tree.contains_key(&join(&dir, name)) || shadows(&tree, &links, &dir, name, id) {
+                    break;
+                }
+                target = dir;
+            }
+            // the nested copy would place the same dependencies its enclosing copy did, again and again
+            if ancestors(&target).iter().any(|dir| !dir.is_empty() && location_name(dir) == name && tree[dir] == id) {
+                let dependent = tree.get(&dependent).and_then(|&id| graph.node(id)).map_or_else(|| "package.json".to_string(), |node| node.descriptors[0].to_string());
+                return Err(Error::Cycle { dependent, dependency: format!("{name}@{range}") });
+            }
+            let location = join(&target, name);
+            tree.insert(location.clone(), id);
+            links.push(Link { from: dependent.clone(), to: location.clone(), kind });
+
+            let Some(node) = graph.node(id) else {
+                continue;
+            };
+            let mut dependencies = vec![];
+            let entry_dependencies = node.entry.dependencies.iter().map(|d| (d, Kind::Prod));
+            let optional_dependencies = node.entry.optional_dependencies.iter().map(|d| (d, Kind::Optional));
+            for ((name, range), kind) in entry_dependencies.chain(optional_dependencies) {
+                let to = graph.resolve(name, range).ok_or_else(|| Error::Unresolved { dependent: node.descriptors[0].to_string(), dependency: format!("{name}@{range}") })?;
+                dependencies.push((name.as_str(), range.as_str(), to, kind));
+            }
+            dependencies.sort_by_key(|(name, ..)| *name);
+            queue.push_back((location, dependencies));
+        }
+    }
+
+    let flags = flags(&tree, &links);
+    let mut packages = vec![(String::new(), root_package(manifest))];
+    for (location, &id) in &tree {
+        let Some(node) = graph.node(id) else {
+            continue;
+        };
+        let entry = node.entry;
+        let mut package = vec![];
+        let real_name = Descriptor::parse(node.descriptors[0]).effective_name();
+        if real_name != location_name(location) {
+            package.push(("name".to_string(), Json::String(real_name)));
+        }
+        package.push(("version".to_string(), Json::String(entry.version.clone())));
+        if let Some(resolved) = &entry.resolved {
+            let resolved = match entry.resolution() {
+                Some(Resolution::Registry { url, .. } | Resolution::Tarball { url, .. }) => url,
+                _ => resolved.clone(),
+            };
+            package.push(("resolved".to_string(), Json::String(resolved)));
+        }
+        let integrity = entry.subresource_integrity().map_or_else(|_| entry.integrity.clone(), |i| i.map(|i| i.to_string()));
+        if let Some(integrity) = integrity {
+            package.push(("integrity".to_string(), Json::String(integrity)));
+        }
+        let flags = flags[location];
+        for (key, set) in [("dev", flags.dev), ("optional", flags.optional), ("devOptional", flags.dev_optional && !flags.dev && !flags.optional)] {
+            if set {
+                package.push((key.to_string(), Json::Bool(true)));
+            }
+        }
+        for (key, map) in [("dependencies", &entry.dependencies), ("optionalDependencies", &entry.optional_dependencies)] {
+            if !map.is_empty() {
+                package.push((key.to_string(), string_map(map)));
+            }
+        }
+        packages.push((location.clone(), Json::Object(package)));
+    }
+
+    let mut lock = vec![];
+    if let Some(name) = &manifest.name {
+        lock.push(("name".to_string(), Json::String(name.clone())));
+    }
+    if let Some(version) = &manifest.version {
+        lock.push(("version".to_string(), Json::String(version.clone())));
+    }
+    lock.push(("lockfileVersion".to_string(), Json::Number("3".to_string())));
+    lock.push(("requires".to_string(), Json::Bool(true)));
+    lock.push(("packages".to_string(), Json::Object(packages)));
+    Ok(Json::Object(lock).to_pretty_string() + "\n")
+}
+
+/// The dependencies of the root manifest npm installs, sorted by name. A package in
+/// `optionalDependencies` is skipped in `dependencies`, and one in either is skipped in
+/// `devDependencies`.
+fn manifest_dependencies(manifest: &Manifest) -> Vec<(&str, &str, Kind)> {
+    let mut dependencies = vec![];
+    for (name, range) in &manifest.dependencies {
+        if !manifest.optional_dependencies.contains_key(name) {
+            dependencies.push((name.as_str(), range.as_str(), Kind::Prod));
+        }
+    }
+    for (name, range) in &manifest.dev_dependencies {
+        if !manifest.dependencies.contains_key(name) && !manifest.optional_dependencies.contains_key(name) {
+            dependencies.push((name.as_str(), range.as_str(), Kind::Dev));
+        }
+    }
+    for (name, range) in &manifest.optional_dependencies {
+        dependencies.push((name.as_str(), range.as_str(), Kind::Optional));
+    }
+    dependencies.sort_by_key(|(name, ..)| *name);
+    dependencies
+}
+
+fn root_package(manifest: &Manifest) -> Json {
+    let mut package = vec![];
+    if let Some(name) = &manifest.name {
+        package.push(("name".to_string(), Json::String(name.clone())));
+    }
+    if let Some(version) = &manifest.version {
+        package.push(("version".to_string(), Json::String(version.clone())));
+    }
+    for (key, map) in [
+        ("dependencies", &manifest.dependencies),
+        ("devDependencies", &manifest.dev_dependencies),
+        ("optionalDependencies", &manifest.optional_dependencies),
+        ("peerDependencies", &manifest.peer_dependencies),
+    ] {
+        if !map.is_empty() {
+            package.push((key.to_string(), string_map(map)));
+        }
+    }
+    Json::Object(package)
+}
+
+fn string_map(map: &BTreeMap<String, String>) -> Json {
+    Json::Object(map.iter().map(|(k, v)| (k.clone(), Json::String(v.clone()))).collect())
+}
+
+/// The location of package `name` in the `node_modules` of `dir`, `""` being the root.
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() { format!("node_modules/{name}") } else { format!("{dir}/node_modules/{name}") }
+}
+
+/// The package name a location installs, e.g. `@scope/b` for `node_modules/a/node_modules/@scope/b`.
+fn location_name(location: &str) -> &str {
+    location.rfind("node_modules/").map_or(location, |i| &location[i + "node_modules/".len()..])
+}
+
+/// The directory and its ancestors whose `node_modules` node would search, ending with the root.
+fn ancestors(dir: &str) -> Vec<String> {
+    let mut dirs = vec![dir.to_string()];
+    let mut dir = dir;
+    while !dir.is_empty() {
+        dir = dir.rfind("/node_modules/").map_or("", |i| &dir[..i]);
+        dirs.push(dir.to_string());
+    }
+    dirs
+}
+
+/// Find the location `name` resolves to from `dir`, like node's module resolution.
+fn lookup<T>(tree: &BTreeMap<String, T>, dir: &str, name: &str) -> Option<String> {
+    ancestors(dir).into_iter().map(|dir| join(&dir, name)).find(|location| tree.contains_key(location))
+}
+
+/// Whether placing package `id` at `name` in `dir` changes what an already placed package below
+/// `dir` resolves `name` to.
+fn shadows(tree: &BTreeMap<String, usize>, links: &[Link], dir: &str, name: &str, id: usize) -> bool {
+    let prefix = join(dir, "");
+    links.iter().any(|link| {
+        let below = dir.is_empty() || link.from == dir || link.from.starts_with(&format!("{dir}/"));
+        below && location_name(&link.to) == name && !link.to.starts_with(&prefix) && tree[&link.to] != id
+    })
+}
+
+/// Compute the flags of every location, following npm's `calc-dep-flags`: a flag is cleared when
+/// the package is reached by a link that does not need it.
+fn flags(tree: &BTreeMap<String, usize>, links: &[Link]) -> BTreeMap<String, Flags> {
+    let mut flags: BTreeMap<String, Flags> = tree.keys().map(|location| (location.clone(), Flags { dev: true, optional: true, dev_optional: true })).collect();
+    let root = Flags { dev: false, optional: false, dev_optional: false };
+    let mut queue = VecDeque::from([String::new()]);
+    while let Some(location) = queue.pop_front() {
+        let from = if location.is_empty() { root } else { flags[&location] };
+        for link in links.iter().filter(|link| link.from == location) {
+            let (dev, optional) = (link.kind == Kind::Dev, link.kind == Kind::Optional);
+            let unset_dev_optional = !from.dev_optional && !from.dev && !from.optional && !dev && !optional;
+            let to = flags.get_mut(&link.to).expect("placed location");
+            let before = (to.dev, to.optional, to.dev_optional);
+            to.dev &= !(unset_dev_optional || (!from.dev && !dev));
+            to.optional &= !(unset_dev_optional || (!from.optional && !optional));
+            to.dev_optional &= !unset_dev_optional;
+            if before != (to.dev, to.optional, to.dev_optional) {
+                queue.push_back(link.to.clone());
+            }
+        }
+    }
+    flags
+}
+
+/// The version of a `lockfileVersion` 1 package installed from a URL, npm does not record it.
+pub const UNKNOWN_VERSION: &str = "0.0.0";
+
+/// A package of a `package-lock.json`: the root project, a workspace, or an installed package.
+#[derive(Default)]
+struct Package {
+    version: Option<String>,
+    resolved: Option<String>,
+    integrity: Option<String>,
+    dependencies: BTreeMap<String, String>,
+    optional_dependencies: BTreeMap<String, String>,
+    dev_dependencies: BTreeMap<String, String>,
+    /// The range a `lockfileVersion` 1 package was installed from, when not from the registry.
+    from: Option<String>,
+    /// A symlink to a workspace or a local directory.
+    link: bool,
+}
+
+/// Parse an npm `package-lock.json` or `npm-shrinkwrap.json` with `lockfileVersion` 1, 2 or 3
+/// into the value shape returned by [`parser::parse`].
+///
+/// Every dependency declared by the root project, a workspace or an installed package is resolved
+/// to the package npm installed for it, like node does, and becomes a `name@range` key. When a key
+/// resolves to several packages, the one closest to the root wins. Linked and bundled packages are
+/// left out, yarn does not lock them either.
+///
+/// `lockfileVersion` 1 does not record the ranges of the root project, so the top-level packages
+/// no other package depends on get their `from` field or their version as the range. It also
+/// stores the URL of a git, tarball or local package as its version, that URL becomes `resolved`
+/// and the version, which is not recorded, becomes [`UNKNOWN_VERSION`].
+///
+/// # Errors
+/// - [`lockfile::Error::Parse`]: When the input is not valid JSON
+/// - Any other [`lockfile::Error`]: When a package does not have the expected shape
+pub fn parse(input: &[u8]) -> Result<Value, lockfile::Error> {
+    let json = Json::parse(input).map_err(|offset| {
+        let kind = if offset < input.len() { ErrorKind::InvalidCharacter } else { ErrorKind::UnexpectedEndOfInput };
+        parser::Error::at(input, Span { start: offset, end: offset + 1 }, kind)
+    })?;
+    if !matches!(json, Json::Object(_)) {
+        return Err(lockfile::Error::InvalidEntry { key: String::new() });
+    }
+    let mut packages = BTreeMap::new();
+    if let Some(members) = json.get("packages") {
+        let Json::Object(members) = members else {
+            return Err(invalid_field("", "packages", "an object"));
+        };
+        for (location, value) in members.iter().filter(|(_, value)| value.get("inBundle") != Some(&Json::Bool(true))) {
+            let mut package = read_package(location, value)?;
+            package.dependencies = string_map_field(location, value, "dependencies")?;
+            package.optional_dependencies = string_map_field(location, value, "optionalDependencies")?;
+            package.dev_dependencies = string_map_field(location, value, "devDependencies")?;
+            packages.insert(location.clone(), package);
+        }
+    } else if let Some(dependencies) = json.get("dependencies") {
+        read_dependencies("", dependencies, &mut packages)?;
+    }
+
+    let mut dependents: Vec<&String> = packages.keys().filter(|location| !packages[*location].link).collect();
+    dependents.sort_by_key(|location| location.matches("node_modules/").count());
+    let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+    let mut targets = BTreeSet::new();
+    for location in dependents {
+        let package = &packages[location];
+        let installed = location.starts_with("node_modules/") || location.contains("/node_modules/");
+        let dev_dependencies = (!installed).then_some(&package.dev_dependencies);
+        for (name, range) in package.dependencies.iter().chain(&package.optional_dependencies).chain(dev_dependencies.into_iter().flatten()) {
+            let Some(target) = lookup(&packages, location, name).filter(|target| !packages[target].link) else {
+                continue;
+            };
+            if let btree_map::Entry::Vacant(vacant) = entries.entry(format!("{name}@{range}")) {
+                vacant.insert(entry(&target, &packages[&target])?);
+            }
+            targets.insert(target);
+        }
+    }
+    if json.get("packages").is_none() {
+        for (location, package) in packages.iter().filter(|(location, _)| !targets.contains(*location)) {
+            let name = location.strip_prefix("node_modules/").filter(|name| !name.contains("/node_modules/"));
+            if let (Some(name), Some(range)) = (name, package.from.as_ref().or(package.version.as_ref())) {
+                if let btree_map::Entry::Vacant(vacant) = entries.entry(format!("{name}@{range}")) {
+                    vacant.insert(entry(location, package)?);
+                }
+            }
+        }
+    }
+    Ok(Value::Object(entries.iter().map(|(key, entry)| (key.clone(), entry.to_value())).collect()))
+}
+
+/// Read the nested `dependencies` of a `lockfileVersion` 1 lockfile into packages by location.
+fn read_dependencies(dir: &str, dependencies: &Json, packages: &mut BTreeMap<String, Package>) -> Result<(), lockfile::Error> {
+    let Json::Object(members) = dependencies else {
+        return Err(invalid_field(dir, "dependencies", "an object"));
+    };
+    for (name, value) in members.iter().filter(|(_, value)| value.get("bundled") != Some(&Json::Bool(true))) {
+        let location = join(dir, name);
+        let mut package = read_package(&location, value)?;
+        package.dependencies = string_map_field(&location, value, "requires")?;
+        package.from = string_field(&location, value, "from")?;
+        // an alias is installed with a version like `npm:string-width@4.2.3`
+        if let Some((_, version)) = package.version.as_deref().and_then(|v| v.strip_prefix("npm:")).and_then(|v| v.rsplit_once('@')) {
+            package.from.clone_from(&package.version);
+            package.version = Some(version.to_string());
+        }
+        if let Some(url) = package.version.take_if(|v| Version::parse(v).is_err() && Resolution::parse(v).is_some()) {
+            package.from.get_or_insert_with(|| url.clone());
+            package.resolved.get_or_insert(url);
+            package.version = Some(UNKNOWN_VERSION.to_string());
+        }
+        if let Some(dependencies) = value.get("dependencies") {
+            read_dependencies(&location, dependencies, packages)?;
+        }
+        packages.insert(location, package);
+    }
+    Ok(())
+}
+
+fn read_package(location: &str, value: &Json) -> Result<Package, lockfile::Error> {
+    if !matches!(value, Json::Object(_)) {
+        return Err(lockfile::Error::InvalidEntry { key: location.to_string() });
+    }
+    Ok(Package {
+        version: string_field(location, value, "version")?,
+        resolved: string_field(location, value, "resolved")?,
+        integrity: string_field(location, value, "integrity")?,
+        link: value.get("link") == Some(&Json::Bool(true)),
+        ..Package::default()
+    })
+}
+
+fn entry(location: &str, package: &Package) -> Result<Entry, lockfile::Error> {
+    Ok(Entry {
+        version: package.version.clone().ok_or_else(|| lockfile::Error::MissingField { key: location.to_string(), field: "version" })?,
+        resolved: package.resolved.clone(),
+        integrity: package.integrity.clone(),
+        dependencies: package.dependencies.clone(),
+        optional_dependencies: package.optional_dependencies.clone(),
+        ..Entry::default()
+    })
+}
+
+fn string_field(location: &str, value: &Json, field: &str) -> Result<Option<String>, lockfile::Error> {
+    match value.get(field) {
+        None => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(invalid_field(location, field, "a string")),
+    }
+}
+
+fn string_map_field(location: &str, value: &Json, field: &str) -> Result<BTreeMap<String, String>, lockfile::Error> {
+    match value.get(field) {
+        None => Ok(BTreeMap::new()),
+        Some(Json::Object(members)) => members
+            .iter()
+            .map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())).ok_or_else(|| invalid_field(location, &format!("{field}.{k}"), "a string")))
+            .collect(),
+        Some(_) => Err(invalid_field(location, field, "an object")),
+    }
+}
+
+fn invalid_field(location: &str, field: &str, expected: &'static str) -> lockfile::Error {
+    lockfile::Error::InvalidField { key: location.to_string(), field: field.to_string(), expected }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = r#"# yarn lockfile v1
+
+
+a@^1.0.0:
+  version "1.0.0"
+  resolved "https://registry.yarnpkg.com/a/-/a-1.0.0.tgz#aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"
+  dependencies:
+    c "^1.0.0"
+  optionalDependencies:
+    f "^1.0.0"
+
+b@^1.0.0:
+  version "1.0.0"
+  resolved "https://registry.yarnpkg.com/b/-/b-1.0.0.tgz"
+  integrity sha512-b
+  dependencies:
+    c "^2.0.0"
+
+c@^1.0.0:
+  version "1.0.0"
+
+c@^2.0.0:
+  version "2.0.0"
+  dependencies:
+    d "^1.0.0"
+
+d@^1.0.0:
+  version "1.0.0"
+
+e@^1.0.0:
+  version "1.0.0"
+  dependencies:
+    d "^1.0.0"
+    g "npm:d@^1.0.0"
+
+f@^1.0.0:
+  version "1.0.0"
+
+"g@npm:d@^1.0.0":
+  version "1.0.0"
+
+unused@^1.0.0:
+  version "1.0.0"
+"#;
+
+    #[test]
+    fn convert_to_package_lock() {
+        let lockfile = Lockfile::parse(LOCKFILE.as_bytes()).unwrap();
+        let manifest = Manifest::parse(br#"{"name": "app", "version": "1.0.0", "dependencies": {"a": "^1.0.0", "b": "^1.0.0"}, "devDependencies": {"e": "^1.0.0"}}"#).unwrap();
+        let expected = r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "lockfileVersion": 3,
+  "requires": true,
+  "packages": {
+    "": {
+      "name": "app",
+      "version": "1.0.0",
+      "dependencies": {
+        "a": "^1.0.0",
+        "b": "^1.0.0"
+      },
+      "devDependencies": {
+        "e": "^1.0.0"
+      }
+    },
+    "node_modules/a": {
+      "version": "1.0.0",
+      "resolved": "https://registry.yarnpkg.com/a/-/a-1.0.0.tgz",
+      "integrity": "sha1-qvTGHdzF6KLavt4PO0gs2a6pQ00=",
+      "dependencies": {
+        "c": "^1.0.0"
+      },
+      "optionalDependencies": {
+        "f": "^1.0.0"
+      }
+    },
+    "node_modules/b": {
+      "version": "1.0.0",
+      "resolved": "https://registry.yarnpkg.com/b/-/b-1.0.0.tgz",
+      "integrity": "sha512-b",
+      "dependencies": {
+        "c": "^2.0.0"
+      }
+    },
+    "node_modules/b/node_modules/c": {
+      "version": "2.0.0",
+      "dependencies": {
+        "d": "^1.0.0"
+      }
+    },
+    "node_modules/c": {
+      "version": "1.0.0"
+    },
+    "node_modules/d": {
+      "version": "1.0.0"
+    },
+    "node_modules/e": {
+      "version": "1.0.0",
+      "dev": true,
+      "dependencies": {
+        "d": "^1.0.0",
+        "g": "npm:d@^1.0.0"
+      }
+    },
+    "node_modules/f": {
+      "version": "1.0.0",
+      "optional": true
+    },
+    "node_modules/g": {
+      "name": "d",
+      "version": "1.0.0",
+      "dev": true
+    }
+  }
+}
+"#;
+        assert_eq!(expected, to_package_lock(&lockfile, &manifest).unwrap());
+    }
+
+    #[test]
+    fn hoisting_does_not_shadow() {
+        // y@2 cannot go next to a/node_modules/k, a would then get it instead of y@1
+        let lockfile = Lockfile::parse(
+            b"a@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    k \"^1.0.0\"\n    y \"^1.0.0\"\n\nk@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    y \"^2.0.0\"\n\nk@^2.0.0:\n  version \"2.0.0\"\n\ny@^1.0.0:\n  version \"1.0.0\"\n\ny@^2.0.0:\n  version \"2.0.0\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::parse(br#"{"dependencies": {"a": "^1.0.0", "k": "^2.0.0"}}"#).unwrap();
+        let lock = to_package_lock(&lockfile, &manifest).unwrap();
+        let locations: Vec<&str> = lock.lines().filter_map(|l| l.trim().strip_prefix("\"node_modules/")?.strip_suffix("\": {")).collect();
+        assert_eq!(vec!["a", "a/node_modules/k", "a/node_modules/k/node_modules/y", "k", "y"], locations);
+        assert!(lock.contains("\"node_modules/a/node_modules/k/node_modules/y\": {\n      \"version\": \"2.0.0\""));
+    }
+
+    #[test]
+    fn nested_cycles() {
+        // b@1 is nested in a, its dependency on a resolves back to the top-level a
+        let lockfile = Lockfile::parse(
+            b"a@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    b \"^1.0.0\"\n\nb@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    a \"^1.0.0\"\n\nb@^2.0.0:\n  version \"2.0.0\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::parse(br#"{"dependencies": {"a": "^1.0.0", "b": "^2.0.0"}}"#).unwrap();
+        let lock = to_package_lock(&lockfile, &manifest).unwrap();
+        let locations: Vec<&str> = lock.lines().filter_map(|l| l.trim().strip_prefix("\"node_modules/")?.strip_suffix("\": {")).collect();
+        assert_eq!(vec!["a", "a/node_modules/b", "b"], locations);
+
+        // a@1 -> b@1 -> a@2 -> b@2 -> a@1 never resolves to an enclosing copy
+        let lockfile = Lockfile::parse(
+            b"a@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    b \"^1.0.0\"\n\na@^2.0.0:\n  version \"2.0.0\"\n  dependencies:\n    b \"^2.0.0\"\n\nb@^1.0.0:\n  version \"1.0.0\"\n  dependencies:\n    a \"^2.0.0\"\n\nb@^2.0.0:\n  version \"2.0.0\"\n  dependencies:\n    a \"^1.0.0\"\n",
+        )
+        .unwrap();
+        let manifest = Manifest::parse(br#"{"dependencies": {"a": "^1.0.0"}}"#).unwrap();
+        let error = Error::Cycle { dependent: "a@^1.0.0".to_string(), dependency: "b@^1.0.0".to_string() };
+        assert_eq!(Err(error), to_package_lock(&lockfile, &manifest));
+    }
+
+    #[test]
+    fn parse_package_lock() {
+        let lockfile = Lockfile::parse(LOCKFILE.as_bytes()).unwrap();
+        let manifest = Manifest::parse(br#"{"dependencies": {"a": "^1.0.0", "b": "^1.0.0"}, "devDependencies": {"e": "^1.0.0"}}"#).unwrap();
+        let imported = Lockfile::parse_npm(to_package_lock(&lockfile, &manifest).unwrap().as_bytes()).unwrap();
+        let mut expected = lockfile;
+        expected.entries.remove("unused@^1.0.0");
+        let a = expected.entries.get_mut("a@^1.0.0").unwrap();
+        a.resolved = Some("https://registry.yarnpkg.com/a/-/a-1.0.0.tgz".to_string());
+        a.integrity = Some("sha1-qvTGHdzF6KLavt4PO0gs2a6pQ00=".to_string());
+        assert_eq!(expected, imported);
+
+        let input = br#"{
+  "lockfileVersion": 3,
+  "packages": {
+    "": {"workspaces": ["packages/w"], "dependencies": {"w": "*", "x": "^1.0.0"}},
+    "node_modules/w": {"resolved": "packages/w", "link": true},
+    "node_modules/x": {"version": "1.2.0", "dependencies": {"y": "~1.0.0"}},
+    "node_modules/x/node_modules/y": {"version": "1.0.1"},
+    "node_modules/x/node_modules/z": {"version": "1.0.0", "inBundle": true},
+    "node_modules/y": {"version": "2.0.0"},
+    "packages/w": {"version": "0.1.0", "devDependencies": {"y": "^2.0.0"}}
+  }
+}"#;
+        let imported = Lockfile::parse_npm(input).unwrap();
+        assert_eq!(vec!["x@^1.0.0", "y@^2.0.0", "y@~1.0.0"], imported.iter().map(|(key, _)| key).collect::<Vec<_>>());
+        assert_eq!("1.0.1", imported.get("y@~1.0.0").unwrap().version);
+    }
+
+    #[test]
+    fn parse_package_lock_v1() {
+        let input = br#"{
+  "name": "app",
+  "lockfileVersion": 1,
+  "requires": true,
+  "dependencies": {
+    "a": {"version": "1.0.0", "resolved": "https://registry.npmjs.org/a/-/a-1.0.0.tgz", "integrity": "sha512-a", "requires": {"b": "^1.0.0", "s": "npm:string-width@^4.0.0"}},
+    "b": {"version": "1.1.0", "dependencies": {"c": {"version": "2.0.0", "bundled": true}}},
+    "r": {"version": "git+https://github.com/user/r.git#abc", "from": "github:user/r"},
+    "s": {"version": "npm:string-width@4.2.3"}
+  }
+}"#;
+        let imported = Lockfile::parse_npm(input).unwrap();
+        assert_eq!(vec!["a@1.0.0", "b@^1.0.0", "r@github:user/r", "s@npm:string-width@^4.0.0"], imported.iter().map(|(key, _)| key).collect::<Vec<_>>());
+        assert_eq!(Some("sha512-a"), imported.get("a@1.0.0").unwrap().integrity.as_deref());
+        assert_eq!("4.2.3", imported.get("s@npm:string-width@^4.0.0").unwrap().version);
+        let r = imported.get("r@github:user/r").unwrap();
+        assert_eq!(&Entry { version: UNKNOWN_VERSION.to_string(), resolved: Some("git+https://github.com/user/r.git#abc".to_string()), ..Entry::default() }, r);
+        assert_eq!(Some(Resolution::Git { host: "github.com".to_string(), repo: "user/r".to_string(), commit: Some("abc".to_string()), url: "git+https://github.com/user/r.git".to_string() }), r.resolution());
+        assert_eq!(imported, Lockfile::parse(imported.to_string().as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn parse_package_lock_errors() {
+        assert!(matches!(parse(b"{\"lockfileVersion\": 3,"), Err(lockfile::Error::Parse(parser::Error { kind: ErrorKind::UnexpectedEndOfInput, .. }))));
+        let err = parse(br#"{"lockfileVersion": 3, "packages": {"": {"dependencies": {"a": "^1.0.0"}}, "node_modules/a": {}}}"#).unwrap_err();
+        assert!(matches!(err, lockfile::Error::MissingField { ref key, field: "version" } if key == "node_modules/a"));
+        let err = parse(br#"{"lockfileVersion": 3, "packages": {"node_modules/a": {"version": 1}}}"#).unwrap_err();
+        assert_eq!("Lockfile error[node_modules/a]: field `version` expected a string", err.to_string());
+    }
+
+    #[test]
+    fn unresolved_dependencies() {
+        let lockfile = Lockfile::parse(LOCKFILE.as_bytes()).unwrap();
+        let manifest = Manifest::parse(br#"{"dependencies": {"missing": "^1.0.0"}}"#).unwrap();
+        let error = Error::Unresolved { dependent: "package.json".to_string(), dependency: "missing@^1.0.0".to_string() };
+        assert_eq!(Err(error), to_package_lock(&lockfile, &manifest));
+    }
+}
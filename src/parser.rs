@@ -1,14 +1,19 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
+use std::hash::Hash;
 use std::rc::Rc;
 
-use crate::lexer::tokenize;
+use crate::lexer::Lexer;
 use crate::tokens::{Token, TokenWrapper};
 
+pub use crate::error::ErrorKind;
+pub use crate::tokens::{Span, TokenKind};
+
 const VERSION_LINE_TEXT: &str = "yarn lockfile v";
 
-fn version_match(chars: &[u8]) -> Option<i32> {
+pub(crate) fn version_match(chars: &[u8]) -> Option<i32> {
     match std::str::from_utf8(chars) {
         Ok(s) => {
             let s = s.trim();
@@ -21,15 +26,89 @@ fn version_match(chars: &[u8]) -> Option<i32> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     String(Rc<String>),
     Number(f64),
     Boolean(bool),
-    Object(HashMap<String, Value>),
+    Object(HashMap<String, Self>),
+    Null,
+}
+
+/// A value borrowing its keys and strings from the input.
+///
+/// Strings are only allocated when a quoted string contains escapes, see [`parse_borrowed`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum BorrowedValue<'a> {
+    String(Cow<'a, str>),
+    Number(f64),
+    Boolean(bool),
+    Object(HashMap<Cow<'a, str>, Self>),
     Null,
 }
 
+impl BorrowedValue<'_> {
+    /// Convert into a [`Value`] that does not borrow from the input.
+    #[must_use]
+    pub fn into_owned(self) -> Value {
+        match self {
+            Self::String(s) => Value::String(Rc::new(s.into_owned())),
+            Self::Number(n) => Value::Number(n),
+            Self::Boolean(b) => Value::Boolean(b),
+            Self::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect()),
+            Self::Null => Value::Null,
+        }
+    }
+}
+
+/// The value types the parser can build.
+trait Node<'t>: Clone {
+    type Key: Eq + Hash;
+    fn key(s: Cow<'t, str>) -> Self::Key;
+    fn string(s: Cow<'t, str>) -> Self;
+    fn number(n: f64) -> Self;
+    fn boolean(b: bool) -> Self;
+    fn object(map: HashMap<Self::Key, Self>) -> Self;
+}
+
+impl<'t> Node<'t> for Value {
+    type Key = String;
+    fn key(s: Cow<'t, str>) -> String {
+        s.into_owned()
+    }
+    fn string(s: Cow<'t, str>) -> Self {
+        Self::String(Rc::new(s.into_owned()))
+    }
+    fn number(n: f64) -> Self {
+        Self::Number(n)
+    }
+    fn boolean(b: bool) -> Self {
+        Self::Boolean(b)
+    }
+    fn object(map: HashMap<String, Self>) -> Self {
+        Self::Object(map)
+    }
+}
+
+impl<'t> Node<'t> for BorrowedValue<'t> {
+    type Key = Cow<'t, str>;
+    fn key(s: Cow<'t, str>) -> Cow<'t, str> {
+        s
+    }
+    fn string(s: Cow<'t, str>) -> Self {
+        Self::String(s)
+    }
+    fn number(n: f64) -> Self {
+        Self::Number(n)
+    }
+    fn boolean(b: bool) -> Self {
+        Self::Boolean(b)
+    }
+    fn object(map: HashMap<Cow<'t, str>, Self>) -> Self {
+        Self::Object(map)
+    }
+}
+
 /// Parsing error.
 ///
 /// This error is returned when the parser encounters an error while parsing the input.
@@ -39,22 +118,77 @@ pub struct Error {
     pub line: i32,
     /// The column number where the error occurred.
     pub col: i32,
-    /// The reason for the error.
-    pub reason: &'static str,
+    /// The byte span in the input where the error occurred.
+    pub span: Span,
+    /// The kind of the error.
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    /// Create an error at the span, computing the line and column from the input.
+    pub(crate) fn at(input: &[u8], span: Span, kind: ErrorKind) -> Self {
+        let (line, line_start) = locate(input, span.start);
+        let col = i32::try_from(span.start - line_start).unwrap_or(i32::MAX);
+        Self { line, col, span, kind }
+    }
+
+    /// Render the error with a caret-annotated snippet of the offending line.
+    ///
+    /// The input must be the one that was parsed.
+    ///
+    /// ```text
+    /// error: Unexpected token Colon
+    ///  --> 4:23
+    ///   |
+    /// 4 | "@colors/colors@1.5.0"::
+    ///   |                       ^
+    /// ```
+    #[must_use]
+    pub fn render(&self, input: &[u8]) -> String {
+        let start = self.span.start.min(input.len());
+        let (line, line_start) = locate(input, start);
+        let line_end = input[line_start..].iter().position(|&b| b == b'\n' || b == b'\r').map_or(input.len(), |p| p + line_start);
+        let text = String::from_utf8_lossy(&input[line_start..line_end]);
+        let prefix = String::from_utf8_lossy(&input[line_start..start]).chars().count();
+        let marked = String::from_utf8_lossy(&input[start..self.span.end.clamp(start, line_end.max(start))]).chars().count();
+        let gutter = " ".repeat(line.to_string().len());
+        format!(
+            "error: {}\n{gutter}--> {line}:{}\n{gutter} |\n{line} | {text}\n{gutter} | {}{}\n",
+            self.kind,
+            prefix + 1,
+            " ".repeat(prefix),
+            "^".repeat(marked.max(1)),
+        )
+    }
+}
+
+/// Find the 1-based line number and the line start offset of the byte offset.
+fn locate(input: &[u8], offset: usize) -> (i32, usize) {
+    let mut line = 1;
+    let mut line_start = 0;
+    let offset = offset.min(input.len());
+    for (i, &b) in input[..offset].iter().enumerate() {
+        if b == b'\n' || (b == b'\r' && input.get(i + 1) != Some(&b'\n')) {
+            line += 1;
+            line_start = i + 1;
+        }
+    }
+    (line, line_start)
 }
 
 impl std::error::Error for Error {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Parsing error[{}:{}]: ", self.line, self.col).and_then(|()| write!(f, "{}", self.reason))
+        write!(f, "Parsing error[{}:{}]: ", self.line, self.col).and_then(|()| write!(f, "{}", self.kind))
     }
 }
 
-struct Parser<'t> {
-    tokens: &'t [TokenWrapper<'t>],
-    token_ptr: usize,
-    cur: &'t TokenWrapper<'t>,
+#[derive(Clone)]
+pub(crate) struct Parser<'t> {
+    input: &'t [u8],
+    lexer: Lexer<'t>,
+    pub(crate) cur: TokenWrapper<'t>,
 }
 
 /// Parse the input yarn lock data.
@@ -66,52 +200,183 @@ struct Parser<'t> {
 /// - [`Error`]: When parsing failed
 ///
 pub fn parse(input: &[u8]) -> Result<Value, Error> {
-    let tokens = &tokenize(input).map_err(|e| Error { line: e.line, col: e.col, reason: e.reason })?;
-    let mut parser = Parser {
-        tokens,
-        token_ptr: 0,
-        cur: &tokens[0],
+    parse_node(input)
+}
+
+/// Parse the input yarn lock data without copying keys and strings out of the input.
+///
+/// Same as [`parse`], but returns a [`BorrowedValue`].
+///
+/// # Errors
+/// - [`Error`]: When parsing failed
+///
+pub fn parse_borrowed(input: &[u8]) -> Result<BorrowedValue<'_>, Error> {
+    parse_node(input)
+}
+
+const MERGE_CONFLICT_ANCESTOR: &[u8] = b"|||||||";
+const MERGE_CONFLICT_END: &[u8] = b">>>>>>>";
+const MERGE_CONFLICT_SEP: &[u8] = b"=======";
+const MERGE_CONFLICT_START: &[u8] = b"<<<<<<<";
+
+/// How [`parse_with_conflicts`] got its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseResultType {
+    /// The input had no merge conflicts.
+    Success,
+    /// The input had merge conflicts, both sides were parsed and merged.
+    Merge,
+    /// The input had merge conflicts, and one of the sides failed to parse.
+    Conflict,
+}
+
+/// The result of [`parse_with_conflicts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseResult {
+    pub kind: ParseResultType,
+    /// The parsed object, empty on [`ParseResultType::Conflict`].
+    pub object: Value,
+}
+
+/// Parse the input yarn lock data, resolving git merge conflicts.
+///
+/// Translated from [https://github.com/yarnpkg/yarn/blob/master/src/lockfile/parse.js#L392](https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/src/lockfile/parse.js#L392)
+///
+/// When the input contains conflict markers, both sides are parsed and merged at the top level,
+/// with entries from the second side winning.
+///
+/// # Errors
+/// - [`Error`]: When the input has no merge conflicts and parsing failed
+///
+pub fn parse_with_conflicts(input: &[u8]) -> Result<ParseResult, Error> {
+    let bom = if input.starts_with(b"\xef\xbb\xbf") { 3 } else { 0 };
+    let str = &input[bom..];
+    if !has_merge_conflicts(str) {
+        let object = parse(str).map_err(|e| Error::at(input, Span { start: e.span.start + bom, end: e.span.end + bom }, e.kind))?;
+        return Ok(ParseResult { kind: ParseResultType::Success, object });
+    }
+    let (ours, theirs) = extract_conflict_variants(str);
+    let merged = match (parse(&ours), parse(&theirs)) {
+        (Ok(Value::Object(mut ours)), Ok(Value::Object(theirs))) => {
+            ours.extend(theirs);
+            ParseResult { kind: ParseResultType::Merge, object: Value::Object(ours) }
+        }
+        _ => ParseResult { kind: ParseResultType::Conflict, object: Value::Object(HashMap::new()) },
     };
-    parser.next()?;
-    parser.parse(0)
+    Ok(merged)
+}
+
+fn has_merge_conflicts(str: &[u8]) -> bool {
+    let includes = |marker: &[u8]| str.windows(marker.len()).any(|w| w == marker);
+    includes(MERGE_CONFLICT_START) && includes(MERGE_CONFLICT_SEP) && includes(MERGE_CONFLICT_END)
+}
+
+fn extract_conflict_variants(str: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut variants: [Vec<&[u8]>; 2] = [vec![], vec![]];
+    let mut lines = str.split(|&c| c == b'\n').map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    let mut skip = false;
+
+    while let Some(line) = lines.next() {
+        if line.starts_with(MERGE_CONFLICT_START) {
+            // get the first variant
+            for conflict_line in lines.by_ref() {
+                if conflict_line == MERGE_CONFLICT_SEP {
+                    skip = false;
+                    break;
+                } else if skip || conflict_line.starts_with(MERGE_CONFLICT_ANCESTOR) {
+                    skip = true;
+                } else {
+                    variants[0].push(conflict_line);
+                }
+            }
+
+            // get the second variant
+            for conflict_line in lines.by_ref() {
+                if conflict_line.starts_with(MERGE_CONFLICT_END) {
+                    break;
+                }
+                variants[1].push(conflict_line);
+            }
+        } else {
+            variants[0].push(line);
+            variants[1].push(line);
+        }
+    }
+
+    let [ours, theirs] = variants;
+    (ours.join(&b'\n'), theirs.join(&b'\n'))
+}
+
+fn parse_node<'t, V: Node<'t>>(input: &'t [u8]) -> Result<V, Error> {
+    Parser::new(input)?.parse(0)
 }
 
 impl<'t> Parser<'t> {
-    fn next(&mut self) -> Result<&'t TokenWrapper<'t>, Error> {
+    /// Create a parser positioned at the first token of the input.
+    pub(crate) fn new(input: &'t [u8]) -> Result<Self, Error> {
+        let mut parser = Parser {
+            input,
+            lexer: Lexer::new(input),
+            cur: TokenWrapper { col: 0, line: 1, token: Token::Eof },
+        };
+        parser.next()?;
+        Ok(parser)
+    }
+
+    /// Create an error at the current token.
+    pub(crate) fn error(&self, kind: ErrorKind) -> Error {
+        Error::at(self.input, self.lexer.span(), kind)
+    }
+
+    pub(crate) fn next(&mut self) -> Result<TokenWrapper<'t>, Error> {
         loop {
-            if self.token_ptr >= self.tokens.len() {
-                return Err(Error { line: 0, col: 0, reason: "Unexpected end of input" });
-            }
-            let tk = &self.tokens[self.token_ptr];
-            self.token_ptr += 1;
+            let tk = match self.lexer.next() {
+                None => return Err(self.error(ErrorKind::UnexpectedEndOfInput)),
+                Some(Err(e)) => return Err(Error::at(self.input, e.span, e.kind)),
+                Some(Ok(tk)) => tk,
+            };
             if let Token::Comment(cm) = tk.token {
                 match version_match(cm) {
                     None => { continue; }
                     Some(v) => {
                         if v > 1 {
-                            return Err(Error { line: 0, col: 0, reason: "Unsupported lockfile version" });
+                            return Err(self.error(ErrorKind::UnsupportedVersion(v)));
                         }
                         continue;
                     }
                 }
-            };
+            }
             self.cur = tk;
             return Ok(tk);
         }
     }
 
+    /// Handle an indent that is not on the level of the current mapping: a line with only spaces
+    /// is skipped, a smaller indent ends the mapping, and a larger one is an error.
+    pub(crate) fn ends_mapping(&mut self, found: usize, expected: usize) -> Result<bool, Error> {
+        let mut lookahead = self.clone();
+        if matches!(lookahead.next()?.token, Token::NewLine | Token::Eof) {
+            self.next()?;
+            return Ok(false);
+        }
+        if found < expected {
+            return Ok(true);
+        }
+        Err(self.error(ErrorKind::UnexpectedIndent { expected, found }))
+    }
+
     #[allow(clippy::too_many_lines)]
-    fn parse(&mut self, indent: usize) -> Result<Value, Error> {
-        let mut map: HashMap<String, Value> = HashMap::new();
+    fn parse<V: Node<'t>>(&mut self, indent: usize) -> Result<V, Error> {
+        let mut map: HashMap<V::Key, V> = HashMap::new();
         macro_rules! unquote_string_token {
-            ($token: expr, $s:expr) => {
-                unquote_string($s).map_err(|s| Error { line: $token.line, col: $token.col, reason: s })
+            ($s:expr) => {
+                unquote_string($s).map_err(|kind| self.error(kind))
             };
         }
         macro_rules! key_check {
-            ($token: expr, $s: expr) => {
+            ($s: expr) => {
                 if $s.is_empty() {
-                    return Err(Error { line: $token.line, col: $token.col, reason: "Expected a key" });
+                    return Err(self.error(ErrorKind::ExpectedKey));
                 }
             };
         }
@@ -128,11 +393,9 @@ impl<'t> Parser<'t> {
                         Token::Indent(n) => {
                             if n == indent {
                                 // all is good, the indent is on our level
-                                _ = self.next();
-                            } else {
-                                // the indentation is less than our level
-                                break;
+                                self.next()?;
                             }
+                            // otherwise the `Token::Indent` arm handles it on the next round
                         }
                         _ => {
                             // if we have no indentation after a newline then we've gone down a level
@@ -142,8 +405,8 @@ impl<'t> Parser<'t> {
                 }
                 Token::Indent(n) => {
                     if n == indent {
-                        _ = self.next();
-                    } else {
+                        self.next()?;
+                    } else if self.ends_mapping(n, indent)? {
                         break;
                     }
                 }
@@ -152,23 +415,23 @@ impl<'t> Parser<'t> {
                 }
                 Token::String(s) => {
                     // property key
-                    let key = unquote_string_token!(prop_token, s)?;
-                    key_check!(prop_token, key);
-                    let mut keys = vec![key];
+                    let key = unquote_string_token!(s)?;
+                    key_check!(key);
+                    let mut keys = vec![V::key(key)];
                     _ = self.next()?;
                     // support multiple keys
                     while self.cur.token == Token::Comma {
-                        _ = self.next();
+                        self.next()?;
                         let key_token = self.cur;
                         match key_token.token {
                             Token::String(s) => {
-                                let key = unquote_string_token!(key_token, s)?;
-                                key_check!(key_token, key);
-                                keys.push(key);
+                                let key = unquote_string_token!(s)?;
+                                key_check!(key);
+                                keys.push(V::key(key));
                                 _ = self.next()?;
                             }
-                            _ => { return Err(Error { line: key_token.line, col: key_token.col, reason: "Expected string" }) }
-                        };
+                            _ => { return Err(self.error(ErrorKind::ExpectedString)) }
+                        }
                     };
                     let was_colon = matches!(self.cur.token, Token::Colon);
                     if was_colon {
@@ -176,7 +439,7 @@ impl<'t> Parser<'t> {
                     }
                     match self.cur.token {
                         Token::String(u) => {
-                            let v = Value::String(Rc::new(unquote_string_token!(self.cur, u)?));
+                            let v = V::string(unquote_string_token!(u)?);
                             for x in keys {
                                 map.insert(x, v.clone());
                             };
@@ -184,77 +447,60 @@ impl<'t> Parser<'t> {
                         }
                         Token::Number(n) => {
                             for x in keys {
-                                map.insert(x, Value::Number(n));
+                                map.insert(x, V::number(n));
                             };
                             self.next()?;
                         }
                         Token::Bool(b) => {
                             for x in keys {
-                                map.insert(x, Value::Boolean(b));
+                                map.insert(x, V::boolean(b));
                             };
                             self.next()?;
                         }
                         _ => {
                             if was_colon {
-                                let v = self.parse(indent + 2)?;
+                                let v: V = self.parse(indent + 2)?;
                                 for x in keys {
                                     map.insert(x, v.clone());
                                 };
-                                if let Token::Indent(_) = self.cur.token {
-                                    if indent == 0 { break; }
-                                };
+                                if indent != 0 && !matches!(self.cur.token, Token::Indent(_)) {
+                                    break;
+                                }
                             } else {
-                                return Err(Error { line: self.cur.line, col: self.cur.col, reason: unexpected_token_string(&self.cur.token) });
+                                return Err(self.error(ErrorKind::UnexpectedToken(TokenKind::from(&self.cur.token))));
                             }
                         }
-                    };
+                    }
                 }
                 _ => {
-                    return Err(Error { line: prop_token.line, col: prop_token.col, reason: unexpected_token_string(&prop_token.token) });
+                    return Err(self.error(ErrorKind::UnexpectedToken(TokenKind::from(&prop_token.token))));
                 }
             }
         };
-        Ok(Value::Object(map))
+        Ok(V::object(map))
     }
 }
 
 
-const fn unexpected_token_string(token: &Token) -> &'static str {
-    macro_rules! u {
-        ($s: expr) => {concat!("Unexpected token ", $s)};
-    }
-    match token {
-        Token::Bool(_) => u!("Bool"),
-        Token::String(_) => u!("String"),
-        Token::Number(_) => u!("Number"),
-        Token::Indent(_) => u!("Indent"),
-        Token::Comment(_) => u!("Comment"),
-        Token::Eof => u!("Eof"),
-        Token::Colon => u!("Colon"),
-        Token::NewLine => u!("NewLine"),
-        Token::Invalid => u!("Invalid"),
-        Token::Comma => u!("Comma"),
-    }
-}
-
-fn unquote_string(input: &[u8]) -> Result<String, &'static str> {
+pub(crate) fn unquote_string(input: &[u8]) -> Result<Cow<'_, str>, ErrorKind> {
     if !input.is_empty() && input[0] == b'"' {
-        unquote_json_string(input).ok_or("Invalid JSON string")
+        if input.len() >= 2 && input[input.len() - 1] == b'"' && !input.contains(&b'\\') {
+            // no escapes, borrow the content between the quotes
+            return std::str::from_utf8(&input[1..input.len() - 1]).map(Cow::Borrowed).map_err(|_| ErrorKind::InvalidString);
+        }
+        unquote_json_string(input).map(Cow::Owned).ok_or(ErrorKind::InvalidString)
     } else {
-        std::str::from_utf8(input).map(ToString::to_string).map_err(|_| "Invalid UTF-8 string")
+        std::str::from_utf8(input).map(Cow::Borrowed).map_err(|_| ErrorKind::InvalidUtf8)
     }
 }
 
-fn unquote_json_string(input: &[u8]) -> Option<String> {
+pub(crate) fn unquote_json_string(input: &[u8]) -> Option<String> {
     let Ok(input) = std::str::from_utf8(input) else { return None };
     let mut begin = false;
     let mut chars = input.chars();
     let mut buffer = String::new();
     loop {
-        let ch = match chars.next() {
-            None => return None,
-            Some(ch) => ch
-        };
+        let ch = chars.next()?;
         if !begin {
             if ch == '"' {
                 begin = true;
@@ -265,10 +511,7 @@ fn unquote_json_string(input: &[u8]) -> Option<String> {
         match ch {
             '"' => return Some(buffer),
             '\\' => {
-                let ch = match chars.next() {
-                    None => return None,
-                    Some(ch) => ch
-                };
+                let ch = chars.next()?;
                 match ch {
                     '"' => buffer.push('"'),
                     '\\' => buffer.push('\\'),
@@ -281,10 +524,7 @@ fn unquote_json_string(input: &[u8]) -> Option<String> {
                     'u' => {
                         let mut hex = String::new();
                         for _ in 0..4 {
-                            let ch = match chars.next() {
-                                None => return None,
-                                Some(ch) => ch
-                            };
+                            let ch = chars.next()?;
                             hex.push(ch);
                         }
                         let Ok(code) = u32::from_str_radix(&hex, 16) else { return None };
@@ -385,10 +625,89 @@ mod tests {
         println!("{:?}", parse(include_bytes!("test.lock.2")).unwrap());
     }
 
+    #[test]
+    fn parse_borrowed2() {
+        let input = include_bytes!("test.lock.2");
+        assert_eq!(parse(input).unwrap(), parse_borrowed(input).unwrap().into_owned());
+    }
+
+    #[test]
+    fn parse_borrowed_only_allocates_escapes() {
+        let BorrowedValue::Object(map) = parse_borrowed(b"a \"plain\"\n\"b\" \"esc\\\"aped\"\n").unwrap() else { panic!() };
+        assert!(matches!(map.keys().next(), Some(Cow::Borrowed(_))));
+        assert!(matches!(&map["a"], BorrowedValue::String(Cow::Borrowed("plain"))));
+        assert!(matches!(&map["b"], BorrowedValue::String(Cow::Owned(s)) if s == "esc\"aped"));
+    }
+
+    #[test]
+    fn parse_top_level_after_nested_object() {
+        let v = parse(b"a:\n  b:\n    c d\n\ne f\n").unwrap();
+        let Value::Object(map) = v else { panic!() };
+        assert_eq!(2, map.len());
+        assert_eq!(Some(&Value::String(Rc::new("f".to_string()))), map.get("e"));
+    }
+
+    #[test]
+    fn error_points_to_real_location() {
+        let input = b"# yarn lockfile v1\n\n\n\"a\"::\n";
+        let err = parse(input).unwrap_err();
+        assert_eq!((4, 4, Span { start: 25, end: 26 }, ErrorKind::UnexpectedToken(TokenKind::Colon)), (err.line, err.col, err.span, err.kind));
+        assert_eq!("error: Unexpected token Colon\n --> 4:5\n  |\n4 | \"a\"::\n  |     ^\n", err.render(input));
+    }
+
+    #[test]
+    fn error_spans() {
+        let err = parse(b"# yarn lockfile v2\n").unwrap_err();
+        assert_eq!((1, 0, Span { start: 0, end: 18 }), (err.line, err.col, err.span));
+        let input = b"a:\n   b c\n";
+        let err = parse(input).unwrap_err();
+        assert_eq!((2, 0, Span { start: 3, end: 6 }, ErrorKind::InvalidIndent { found: 3 }), (err.line, err.col, err.span, err.kind));
+        assert_eq!("error: Invalid number of spaces: 3\n --> 2:1\n  |\n2 |    b c\n  | ^^^\n", err.render(input));
+        let err = parse(b"a \"b\\u00\"\r\n").unwrap_err();
+        assert_eq!((1, 2, Span { start: 2, end: 9 }, ErrorKind::InvalidString), (err.line, err.col, err.span, err.kind));
+    }
+
+    #[test]
+    fn unexpected_indents() {
+        let input = b"a:\n  b c\n    d e\n";
+        let err = parse(input).unwrap_err();
+        assert_eq!((3, 0, Span { start: 9, end: 13 }, ErrorKind::UnexpectedIndent { expected: 2, found: 4 }), (err.line, err.col, err.span, err.kind));
+        assert_eq!("Parsing error[3:0]: Unexpected indentation: expected 2 spaces, found 4", err.to_string());
+        let err = parse(b"a b\n  c d\n").unwrap_err();
+        assert_eq!(ErrorKind::UnexpectedIndent { expected: 0, found: 2 }, err.kind);
+        // lines with only spaces are skipped, a smaller indent ends the mapping
+        let expected = parse(b"a:\n  b:\n    c d\n  e f\ng h\n").unwrap();
+        assert_eq!(expected, parse(b"a:\n  b:\n    c d\n      \n  e f\n  \ng h\n").unwrap());
+    }
+
+    #[test]
+    fn parse_with_conflicts_success() {
+        let r = parse_with_conflicts(b"\xef\xbb\xbfa \"1\"\n").unwrap();
+        assert_eq!(ParseResultType::Success, r.kind);
+        assert_eq!(parse(b"a \"1\"\n").unwrap(), r.object);
+        let err = parse_with_conflicts(b"\xef\xbb\xbfa::\n").unwrap_err();
+        assert_eq!((1, 5, Span { start: 5, end: 6 }), (err.line, err.col, err.span));
+    }
+
+    #[test]
+    fn parse_with_conflicts_merge() {
+        let input = b"a:\n  version \"1\"\n<<<<<<< HEAD\r\nb:\n  version \"1\"\n||||||| base\nb:\n  version \"0\"\n=======\nb:\n  version \"2\"\nc:\n  version \"3\"\n>>>>>>> theirs\n";
+        let r = parse_with_conflicts(input).unwrap();
+        assert_eq!(ParseResultType::Merge, r.kind);
+        let expected = parse(b"a:\n  version \"1\"\nb:\n  version \"2\"\nc:\n  version \"3\"\n").unwrap();
+        assert_eq!(expected, r.object);
+    }
+
+    #[test]
+    fn parse_with_conflicts_conflict() {
+        let r = parse_with_conflicts(b"<<<<<<< HEAD\na \"1\"\n=======\na::\n>>>>>>> theirs\n").unwrap();
+        assert_eq!(ParseResult { kind: ParseResultType::Conflict, object: Value::Object(HashMap::new()) }, r);
+    }
+
     #[test]
     fn test_parse_err() {
         const fn foo() -> Result<(), Error> {
-            let pe = Error { line: 1, col: 1, reason: "test" };
+            let pe = Error { line: 1, col: 1, span: Span { start: 0, end: 1 }, kind: ErrorKind::ExpectedKey };
             Err(pe)
         }
 
@@ -397,7 +716,7 @@ mod tests {
             Ok(())
         }
 
-        let pe = Error { line: 1, col: 1, reason: "test" };
+        let pe = Error { line: 1, col: 1, span: Span { start: 0, end: 1 }, kind: ErrorKind::ExpectedKey };
         let ee: Box<dyn std::error::Error> = Box::new(pe);
         println!("{ee}");
 
@@ -406,15 +725,18 @@ mod tests {
     
     #[test]
     fn test_unexpected_token_string(){
-        assert_eq!(unexpected_token_string(&Token::Bool(true)), "Unexpected token Bool");
-        assert_eq!(unexpected_token_string(&Token::String(&[])), "Unexpected token String");
-        assert_eq!(unexpected_token_string(&Token::Number(0.0)), "Unexpected token Number");
-        assert_eq!(unexpected_token_string(&Token::Indent(0)), "Unexpected token Indent");
-        assert_eq!(unexpected_token_string(&Token::Comment(&[])), "Unexpected token Comment");
-        assert_eq!(unexpected_token_string(&Token::Eof), "Unexpected token Eof");
-        assert_eq!(unexpected_token_string(&Token::Colon), "Unexpected token Colon");
-        assert_eq!(unexpected_token_string(&Token::NewLine), "Unexpected token NewLine");
-        assert_eq!(unexpected_token_string(&Token::Invalid), "Unexpected token Invalid");
-        assert_eq!(unexpected_token_string(&Token::Comma), "Unexpected token Comma");
+        fn u(token: &Token) -> String {
+            ErrorKind::UnexpectedToken(TokenKind::from(token)).to_string()
+        }
+        assert_eq!(u(&Token::Bool(true)), "Unexpected token Bool");
+        assert_eq!(u(&Token::String(&[])), "Unexpected token String");
+        assert_eq!(u(&Token::Number(0.0)), "Unexpected token Number");
+        assert_eq!(u(&Token::Indent(0)), "Unexpected token Indent");
+        assert_eq!(u(&Token::Comment(&[])), "Unexpected token Comment");
+        assert_eq!(u(&Token::Eof), "Unexpected token Eof");
+        assert_eq!(u(&Token::Colon), "Unexpected token Colon");
+        assert_eq!(u(&Token::NewLine), "Unexpected token NewLine");
+        assert_eq!(u(&Token::Invalid), "Unexpected token Invalid");
+        assert_eq!(u(&Token::Comma), "Unexpected token Comma");
     }
 }
@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::descriptor::Descriptor;
+use crate::diff::{diff, VersionChange};
+use crate::lockfile::Lockfile;
+use crate::manifest::Manifest;
+use crate::resolution::Resolution;
+
+/// Render the changes between two lockfiles as Markdown, for a pull request comment.
+///
+/// The report has a table of added, removed and changed packages each, the versions whose
+/// `resolved` or `integrity` changed without a version change, and the registries, git
+/// repositories and other sources only the new lockfile fetches from. With a manifest, every
+/// package is marked as a direct or a transitive dependency.
+#[must_use]
+pub fn markdown(old: &Lockfile, new: &Lockfile, manifest: Option<&Manifest>) -> String {
+    let diff = diff(old, new);
+    let direct: Option<BTreeSet<&str>> = manifest.map(|m| m.installed_dependencies().map(|(name, _)| name).collect());
+    let kind = |name: &str| direct.as_ref().map(|direct| if direct.contains(name) { "direct" } else { "transitive" });
+    let header = |columns: &[&str]| {
+        let columns: Vec<&str> = columns.iter().copied().chain(direct.as_ref().map(|_| "Type")).collect();
+        format!("| {} |\n|{}\n", columns.join(" | "), "---|".repeat(columns.len()))
+    };
+    let row = |cells: &[String], name: &str| {
+        let cells: Vec<String> = cells.iter().cloned().chain(kind(name).map(str::to_string)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut out = String::from("## Lockfile changes\n\n");
+    let changed = diff.upgraded.len() + diff.downgraded.len();
+    let _ = writeln!(out, "{} added, {} removed, {changed} changed, {} with another source.", diff.added.len(), diff.removed.len(), diff.changed.len());
+    let sources = new_sources(old, new);
+    if diff.added.is_empty() && changed == 0 && diff.removed.is_empty() && diff.changed.is_empty() && sources.is_empty() {
+        return out;
+    }
+
+    if !diff.added.is_empty() {
+        out.push_str("\n### Added\n\n");
+        out.push_str(&header(&["Package", "Version"]));
+        for package in &diff.added {
+            out.push_str(&row(&[code(&package.name), escape(&package.version)], &package.name));
+        }
+    }
+    if !diff.removed.is_empty() {
+        out.push_str("\n### Removed\n\n");
+        out.push_str(&header(&["Package", "Version"]));
+        for package in &diff.removed {
+            out.push_str(&row(&[code(&package.name), escape(&package.version)], &package.name));
+        }
+    }
+    if changed > 0 {
+        out.push_str("\n### Changed\n\n");
+        out.push_str(&header(&["Package", "From", "To", "Bump"]));
+        let mut version_changes: Vec<(&VersionChange, bool)> = diff.upgraded.iter().map(|c| (c, false)).chain(diff.downgraded.iter().map(|c| (c, true))).collect();
+        version_changes.sort();
+        for (change, downgrade) in version_changes {
+            let bump = change.bump().map_or_else(|| "-".to_string(), |bump| bump.to_string());
+            let bump = if downgrade { format!("{bump} (downgrade)") } else { bump };
+            out.push_str(&row(&[code(&change.name), escape(&change.from), escape(&change.to), bump], &change.name));
+        }
+    }
+    if !diff.changed.is_empty() {
+        out.push_str("\n### Same version, another source\n\n");
+        out.push_str("These versions did not change, but are fetched from another URL or have another checksum.\n\n");
+        out.push_str(&header(&["Package", "Version", "Field", "Old", "New"]));
+        for change in &diff.changed {
+            for (field, values) in [("resolved", &change.resolved), ("integrity", &change.integrity)] {
+                if let Some((old, new)) = values {
+                    let value = |v: &Option<String>| v.as_deref().map_or_else(|| "-".to_string(), code);
+                    out.push_str(&row(&[code(&change.name), escape(&change.version), field.to_string(), value(old), value(new)], &change.name));
+                }
+            }
+        }
+    }
+    if !sources.is_empty() {
+        out.push_str("\n### New sources\n\n");
+        out.push_str("| Source | Packages |\n|---|---|\n");
+        for (source, packages) in &sources {
+            let packages: Vec<String> = packages.iter().map(|p| code(p)).collect();
+            let _ = writeln!(out, "| {} | {} |", source, packages.join(", "));
+        }
+    }
+    out
+}
+
+/// The sources only the new lockfile fetches from, with the packages fetched from them.
+fn new_sources(old: &Lockfile, new: &Lockfile) -> BTreeMap<String, BTreeSet<String>> {
+    let old_sources: BTreeSet<String> = old.iter().filter_map(|(_, entry)| entry.resolution()).map(|r| source(&r)).collect();
+    let mut sources: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (descriptor, entry) in new.iter() {
+        let Some(source) = entry.resolution().map(|r| source(&r)).filter(|s| !old_sources.contains(s)) else {
+            continue;
+        };
+        sources.entry(source).or_default().insert(format!("{}@{}", Descriptor::parse(descriptor).name, entry.version));
+    }
+    sources
+}
+
+/// A source a package is fetched from: a registry host, a git repository, a tarball host, or a
+/// local path.
+fn source(resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Registry { host, .. } => format!("registry {}", code(host)),
+        Resolution::Git { host, repo, .. } => format!("git {}", code(&format!("{host}/{repo}"))),
+        Resolution::Tarball { url, .. } => {
+            let host = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest.split('/').next().unwrap_or(rest));
+            format!("tarball {}", code(host))
+        }
+        Resolution::Local { path } => format!("local {}", code(path)),
+    }
+}
+
+/// A code span, a `|` would end the table cell.
+fn code(s: &str) -> String {
+    format!("`{}`", escape(s))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Entry;
+
+    fn entry(version: &str, resolved: &str) -> Entry {
+        Entry { version: version.to_string(), resolved: Some(resolved.to_string()), ..Entry::default() }
+    }
+
+    #[test]
+    fn render_markdown() {
+        let registry = |name: &str, version: &str| format!("https://registry.yarnpkg.com/{name}/-/{name}-{version}.tgz");
+        let mut old = Lockfile::default();
+        old.entries.insert("a@^1.0.0".to_string(), entry("1.0.0", &registry("a", "1.0.0")));
+        old.entries.insert("b@^2.0.0".to_string(), entry("2.1.0", &registry("b", "2.1.0")));
+        old.entries.insert("c@^1.0.0".to_string(), entry("1.0.0", &registry("c", "1.0.0")));
+        old.entries.insert("d@^1.0.0".to_string(), entry("1.0.0", &registry("d", "1.0.0")));
+        let mut new = Lockfile::default();
+        new.entries.insert("a@^2.0.0".to_string(), entry("2.0.0", &registry("a", "2.0.0")));
+        new.entries.insert("b@~2.0.0".to_string(), entry("2.0.3", &registry("b", "2.0.3")));
+        new.entries.insert("d@^1.0.0".to_string(), entry("1.0.0", "https://npm.example.com/d/-/d-1.0.0.tgz"));
+        new.entries.insert("e@user/e".to_string(), entry("0.1.0", "https://codeload.github.com/user/e/tar.gz/abc"));
+        let manifest = Manifest::parse(br#"{"dependencies": {"a": "^2.0.0", "e": "user/e"}}"#).unwrap();
+
+        assert_eq!(
+            "## Lockfile changes
+
+1 added, 1 removed, 2 changed, 1 with another source.
+
+### Added
+
+| Package | Version | Type |
+|---|---|---|
+| `e` | 0.1.0 | direct |
+
+### Removed
+
+| Package | Version | Type |
+|---|---|---|
+| `c` | 1.0.0 | transitive |
+
+### Changed
+
+| Package | From | To | Bump | Type |
+|---|---|---|---|---|
+| `a` | 1.0.0 | 2.0.0 | major | direct |
+| `b` | 2.1.0 | 2.0.3 | minor (downgrade) | transitive |
+
+### Same version, another source
+
+These versions did not change, but are fetched from another URL or have another checksum.
+
+| Package | Version | Field | Old | New | Type |
+|---|---|---|---|---|---|
+| `d` | 1.0.0 | resolved | `https://registry.yarnpkg.com/d/-/d-1.0.0.tgz` | `https://npm.example.com/d/-/d-1.0.0.tgz` | transitive |
+
+### New sources
+
+| Source | Packages |
+|---|---|
+| git `github.com/user/e` | `e@0.1.0` |
+| registry `npm.example.com` | `d@1.0.0` |
+",
+            markdown(&old, &new, Some(&manifest))
+        );
+        let report = markdown(&old, &new, None);
+        assert!(report.contains("| Package | Version |\n|---|---|\n| `e` | 0.1.0 |\n"));
+        assert_eq!("## Lockfile changes\n\n0 added, 0 removed, 0 changed, 0 with another source.\n", markdown(&old, &old, None));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::descriptor::GIT_HOSTS;
+
+/// The source a package was fetched from, parsed from the `resolved` field of an entry.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    /// A tarball on a npm registry, e.g.
+    /// `https://registry.yarnpkg.com/@babel/core/-/core-7.0.0.tgz#<sha1>`.
+    Registry {
+        /// The registry host, e.g. `registry.yarnpkg.com`.
+        host: String,
+        /// The package name, e.g. `@babel/core`.
+        package: String,
+        /// The tarball file name, e.g. `core-7.0.0.tgz`.
+        filename: String,
+        /// The URL without the fragment.
+        url: String,
+        /// The hex sha1 checksum in the fragment, written by older yarn versions.
+        sha1: Option<String>,
+    },
+    /// Any other tarball URL.
+    Tarball {
+        /// The URL without the fragment.
+        url: String,
+        /// The hex sha1 checksum in the fragment.
+        sha1: Option<String>,
+    },
+    /// A git repository, including GitHub shorthands and `codeload.github.com` tarballs.
+    Git {
+        /// The git host, e.g. `github.com`.
+        host: String,
+        /// The repository path without the `.git` suffix, e.g. `user/repo`.
+        repo: String,
+        /// The commit, tag or branch in the fragment.
+        commit: Option<String>,
+        /// The URL without the fragment.
+        url: String,
+    },
+    /// A local path, e.g. `file:../pkg`.
+    Local {
+        /// The path without the `file:` protocol.
+        path: String,
+    },
+}
+
+impl Resolution {
+    /// Parse a `resolved` URL.
+    ///
+    /// Returns `None` when the URL is not recognized.
+    #[must_use]
+    pub fn parse(resolved: &str) -> Option<Self> {
+        let (url, fragment) = match resolved.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment).filter(|f| !f.is_empty())),
+            None => (resolved, None),
+        };
+        if let Some(path) = url.strip_prefix("file:") {
+            return Some(Self::Local { path: path.to_string() });
+        }
+        if ["./", "../", "/", "~/"].iter().any(|p| url.starts_with(p)) {
+            return Some(Self::Local { path: url.to_string() });
+        }
+        if let Some(git) = parse_git(url, fragment) {
+            return Some(git);
+        }
+        let (scheme, host, path) = split_url(url)?;
+        if scheme != "http" && scheme != "https" {
+            return None;
+        }
+        let sha1 = fragment.filter(|f| f.len() == 40 && f.bytes().all(|c| c.is_ascii_hexdigit())).map(str::to_string);
+        if let Some((package, filename)) = registry_path(path) {
+            return Some(Self::Registry { host: host.to_string(), package, filename: filename.to_string(), url: url.to_string(), sha1 });
+        }
+        Some(Self::Tarball { url: url.to_string(), sha1 })
+    }
+}
+
+/// Split a URL into scheme, host and path, dropping the user and port. An scp-like
+/// `user@host:path` authority is also accepted.
+fn split_url(url: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let rest = &rest[rest[..authority_end].rfind('@').map_or(0, |i| i + 1)..];
+    let host_end = rest.find([':', '/']).unwrap_or(rest.len());
+    let path = rest[host_end..].strip_prefix(':').map_or(&rest[host_end..], |after| {
+        let port_len = after.bytes().take_while(u8::is_ascii_digit).count();
+        let after_port = &after[port_len..];
+        // `git+ssh://git@github.com:user/repo.git` has no port, but a path
+        if port_len > 0 && (after_port.is_empty() || after_port.starts_with('/')) { after_port } else { after }
+    });
+    Some((scheme, &rest[..host_end], path))
+}
+
+fn parse_git(url: &str, fragment: Option<&str>) -> Option<Resolution> {
+    let git = |host: &str, repo: &str| {
+        let repo = repo.trim_matches(['/', ':']);
+        let repo = repo.strip_suffix(".git").unwrap_or(repo);
+        Some(Resolution::Git { host: host.to_string(), repo: repo.to_string(), commit: fragment.map(str::to_string), url: url.to_string() })
+    };
+    for (prefix, host) in [("github:", "github.com"), ("gitlab:", "gitlab.com"), ("bitbucket:", "bitbucket.org")] {
+        if let Some(repo) = url.strip_prefix(prefix) {
+            return git(host, repo);
+        }
+    }
+    if let Some(rest) = url.strip_prefix("https://codeload.github.com/") {
+        // https://codeload.github.com/user/repo/tar.gz/<commit>
+        let parts: Vec<&str> = rest.split('/').collect();
+        if let [user, repo, "tar.gz", commit] = parts[..] {
+            let url = url.to_string();
+            return Some(Resolution::Git { host: "github.com".to_string(), repo: format!("{user}/{repo}"), commit: Some(commit.to_string()), url });
+        }
+        return None;
+    }
+    let Some((scheme, host, path)) = split_url(url) else {
+        // `user/repo` shorthand, or scp-like `git@github.com:user/repo.git`
+        if let Some((authority, repo)) = url.split_once(':').filter(|(a, _)| a.contains('@')) {
+            return git(authority.rsplit_once('@')?.1, repo);
+        }
+        let (user, repo) = url.split_once('/')?;
+        let valid = |s: &str| !s.is_empty() && !s.contains(|c: char| matches!(c, ':' | '@' | '%' | '/') || c.is_whitespace());
+        return (valid(user) && valid(repo) && !user.starts_with(['.', '-'])).then(|| git("github.com", &format!("{user}/{repo}")))?;
+    };
+    let is_git = scheme.starts_with("git+")
+        || scheme == "git"
+        || scheme == "ssh"
+        || path.strip_suffix(".git").is_some()
+        || (GIT_HOSTS.contains(&host) && path.split('/').filter(|p| !p.is_empty()).count() == 2);
+    if is_git {
+        git(host, path)
+    } else {
+        None
+    }
+}
+
+/// Split a registry tarball path like `/@scope/name/-/name-1.0.0.tgz` into package name and file
+/// name. Any path before the package name, like a registry prefix, is skipped.
+fn registry_path(path: &str) -> Option<(String, &str)> {
+    let (package_path, filename) = path.rsplit_once("/-/")?;
+    if filename.contains('/') || filename.is_empty() {
+        return None;
+    }
+    let package_path = package_path.replace("%2f", "/").replace("%2F", "/");
+    let mut segments = package_path.rsplit('/').filter(|s| !s.is_empty());
+    let name = segments.next()?;
+    let package = match segments.next() {
+        Some(scope) if scope.starts_with('@') => format!("{scope}/{name}"),
+        _ => name.to_string(),
+    };
+    Some((package, filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(host: &str, package: &str, filename: &str, url: &str, sha1: Option<&str>) -> Resolution {
+        Resolution::Registry { host: host.to_string(), package: package.to_string(), filename: filename.to_string(), url: url.to_string(), sha1: sha1.map(str::to_string) }
+    }
+
+    fn git(host: &str, repo: &str, commit: Option<&str>, url: &str) -> Resolution {
+        Resolution::Git { host: host.to_string(), repo: repo.to_string(), commit: commit.map(str::to_string), url: url.to_string() }
+    }
+
+    #[test]
+    fn parse_registry() {
+        let url = "https://registry.yarnpkg.com/@colors/colors/-/colors-1.5.0.tgz";
+        let sha1 = "bb504579c1cae923e6576a4f5da43d25f97bdbd9";
+        assert_eq!(Some(registry("registry.yarnpkg.com", "@colors/colors", "colors-1.5.0.tgz", url, Some(sha1))), Resolution::parse(&format!("{url}#{sha1}")));
+        let url = "https://npm.example.com:8443/api/npm/remote/@scope%2fname/-/name-1.0.0.tgz";
+        assert_eq!(Some(registry("npm.example.com", "@scope/name", "name-1.0.0.tgz", url, None)), Resolution::parse(url));
+        let url = "http://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz";
+        assert_eq!(Some(registry("registry.npmjs.org", "lodash", "lodash-4.17.21.tgz", url, None)), Resolution::parse(&format!("{url}#not-a-sha1")));
+    }
+
+    #[test]
+    fn parse_tarball_and_local() {
+        let url = "https://example.com/files/pkg-1.0.0.tgz";
+        assert_eq!(Some(Resolution::Tarball { url: url.to_string(), sha1: None }), Resolution::parse(url));
+        let url = "https://github.com/user/repo/archive/v1.0.0.tar.gz";
+        assert_eq!(Some(Resolution::Tarball { url: url.to_string(), sha1: None }), Resolution::parse(url));
+        assert_eq!(Some(Resolution::Local { path: "../pkg".to_string() }), Resolution::parse("file:../pkg"));
+        assert_eq!(Some(Resolution::Local { path: "./pkg.tgz".to_string() }), Resolution::parse("./pkg.tgz#abc"));
+        assert_eq!(None, Resolution::parse("ftp://example.com/pkg.tgz"));
+        assert_eq!(None, Resolution::parse("not a url"));
+    }
+
+    #[test]
+    fn parse_git() {
+        let sha = "200d2f5e02de694e3e9ffbe177c279bc40240fb8";
+        for (resolved, expected) in [
+            (format!("https://github.com/pgadmin-org/react-data-grid.git#{sha}"), git("github.com", "pgadmin-org/react-data-grid", Some(sha), "https://github.com/pgadmin-org/react-data-grid.git")),
+            (format!("https://github.com/pgadmin-org/wcdocker#{sha}"), git("github.com", "pgadmin-org/wcdocker", Some(sha), "https://github.com/pgadmin-org/wcdocker")),
+            (format!("git+ssh://git@gitlab.com/group/repo.git#{sha}"), git("gitlab.com", "group/repo", Some(sha), "git+ssh://git@gitlab.com/group/repo.git")),
+            (format!("git+ssh://git@github.com:user/repo.git#{sha}"), git("github.com", "user/repo", Some(sha), "git+ssh://git@github.com:user/repo.git")),
+            ("git://git.example.com:9418/repo".to_string(), git("git.example.com", "repo", None, "git://git.example.com:9418/repo")),
+            ("git@github.com:user/repo.git#v1".to_string(), git("github.com", "user/repo", Some("v1"), "git@github.com:user/repo.git")),
+            ("github:user/repo#main".to_string(), git("github.com", "user/repo", Some("main"), "github:user/repo")),
+            ("user/repo".to_string(), git("github.com", "user/repo", None, "user/repo")),
+            (format!("https://codeload.github.com/user/repo/tar.gz/{sha}"), git("github.com", "user/repo", Some(sha), &format!("https://codeload.github.com/user/repo/tar.gz/{sha}"))),
+        ] {
+            assert_eq!(Some(expected), Resolution::parse(&resolved), "{resolved}");
+        }
+    }
+}
@@ -0,0 +1,588 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// A semantic version, see [https://semver.org](https://semver.org).
+///
+/// Parsing is loose like node-semver's `loose` option: surrounding whitespace and a leading `v` or
+/// `=` are accepted. Build metadata is kept, but ignored when comparing.
+#[derive(Clone, Debug)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// The prerelease identifiers, e.g. `beta` and `1` for `1.0.0-beta.1`.
+    pub pre: Vec<Prerelease>,
+    /// The build metadata identifiers, e.g. `sha` and `abc` for `1.0.0+sha.abc`.
+    pub build: Vec<String>,
+}
+
+/// A prerelease identifier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Prerelease {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+/// Semver error.
+///
+/// This error is returned when the input is not a valid version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid version: {}", self.input)
+    }
+}
+
+/// A range of versions, evaluated like [node-semver](https://github.com/npm/node-semver) ranges.
+///
+/// The range is a union of comparator sets, a version satisfies the range when it satisfies all
+/// comparators of any set. Caret, tilde, x-ranges and hyphen ranges are expanded to comparators
+/// when parsing, e.g. `^1.2.0` becomes `>=1.2.0 <2.0.0-0`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Range {
+    /// The comparator sets, an empty set matches any version without prerelease identifiers.
+    pub set: Vec<Vec<Comparator>>,
+}
+
+/// A comparison against a single version, e.g. `>=1.2.0`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparator {
+    pub op: Op,
+    pub version: Version,
+}
+
+/// The operator of a [`Comparator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+/// Semver range error.
+///
+/// This error is returned when the input is not a valid range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeError {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
+impl std::error::Error for RangeError {}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid range: {}", self.input)
+    }
+}
+
+impl Version {
+    #[must_use]
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self { major, minor, patch, pre: vec![], build: vec![] }
+    }
+
+    /// Parse a version.
+    ///
+    /// # Errors
+    /// - [`Error`]: When the input is not a valid version
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let err = || Error { input: input.to_string() };
+        let s = input.trim();
+        let s = s.strip_prefix('=').unwrap_or(s).trim_start();
+        let s = s.strip_prefix('v').unwrap_or(s);
+        let (s, build) = match s.split_once('+') {
+            Some((s, build)) => (s, build.split('.').map(str::to_string).collect::<Vec<_>>()),
+            None => (s, vec![]),
+        };
+        if build.iter().any(|id| !is_identifier(id)) {
+            return Err(err());
+        }
+        let (s, pre) = match s.split_once('-') {
+            Some((s, pre)) => (s, pre.split('.').map(parse_prerelease).collect::<Option<Vec<_>>>().ok_or_else(err)?),
+            None => (s, vec![]),
+        };
+        let mut parts = s.split('.').map(parse_numeric);
+        let (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(err());
+        };
+        Ok(Self { major, minor, patch, pre, build })
+    }
+
+    /// Returns `true` if the version has prerelease identifiers.
+    #[must_use]
+    pub const fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+}
+
+impl Range {
+    /// Parse a range, e.g. `^1.2.0 || >=2.1.0 <3`.
+    ///
+    /// Translated from [https://github.com/npm/node-semver/blob/main/classes/range.js](https://github.com/npm/node-semver/blob/v7.6.3/classes/range.js#L86),
+    /// with the `loose` option and without `includePrerelease`.
+    ///
+    /// # Errors
+    /// - [`RangeError`]: When the input is not a valid range
+    pub fn parse(input: &str) -> Result<Self, RangeError> {
+        let err = || RangeError { input: input.to_string() };
+        let set = input.split("||").map(|range| parse_comparator_set(range).ok_or_else(err)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { set })
+    }
+
+    /// Returns `true` if the version is in the range.
+    ///
+    /// Like node-semver, a prerelease version only satisfies a comparator set that has a
+    /// prerelease version on the same `major.minor.patch`, so `^1.0.0-beta.1` accepts
+    /// `1.0.0-beta.2` but `^1.0.0` does not accept `1.1.0-beta.1`.
+    #[must_use]
+    pub fn satisfies(&self, version: &Version) -> bool {
+        self.set.iter().any(|set| {
+            set.iter().all(|c| c.matches(version))
+                && (!version.is_prerelease()
+                    || set.iter().any(|c| {
+                        c.version.is_prerelease() && (c.version.major, c.version.minor, c.version.patch) == (version.major, version.minor, version.patch)
+                    }))
+        })
+    }
+}
+
+impl Comparator {
+    /// Returns `true` if the version matches the comparator, ignoring prerelease rules.
+    #[must_use]
+    pub fn matches(&self, version: &Version) -> bool {
+        let ordering = version.cmp(&self.version);
+        match self.op {
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+            Op::Eq => ordering.is_eq(),
+        }
+    }
+}
+
+impl Op {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Eq => "",
+        }
+    }
+}
+
+/// A version that may have `x`, `*` or missing parts, e.g. `1.x` or `1.2`.
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Prerelease>,
+}
+
+impl Partial {
+    fn version(&self) -> Version {
+        Version { major: self.major.unwrap_or(0), minor: self.minor.unwrap_or(0), patch: self.patch.unwrap_or(0), pre: self.pre.clone(), build: vec![] }
+    }
+}
+
+/// `major.minor.patch-0`, the lowest version of `major.minor.patch` including prereleases.
+fn lowest(major: u64, minor: u64, patch: u64) -> Version {
+    Version { major, minor, patch, pre: vec![Prerelease::Numeric(0)], build: vec![] }
+}
+
+const fn comparator(op: Op, version: Version) -> Comparator {
+    Comparator { op, version }
+}
+
+fn parse_comparator_set(range: &str) -> Option<Vec<Comparator>> {
+    // join operators separated from their version by whitespace, e.g. `>= 1.2.0`
+    let mut tokens: Vec<String> = vec![];
+    for token in range.split_whitespace() {
+        match tokens.last_mut() {
+            Some(last) if last.bytes().all(|c| matches!(c, b'<' | b'>' | b'=' | b'~' | b'^')) => last.push_str(token),
+            _ => tokens.push(token.to_string()),
+        }
+    }
+    if let [from, "-", to] = tokens.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        return parse_hyphen(from, to);
+    }
+    let mut set = vec![];
+    for token in &tokens {
+        set.extend(parse_comparator(token)?);
+    }
+    Some(set)
+}
+
+/// `1.2 - 2.3.4` is `>=1.2.0 <=2.3.4`, a partial upper version includes every version it matches.
+fn parse_hyphen(from: &str, to: &str) -> Option<Vec<Comparator>> {
+    let (from, to) = (parse_partial(from)?, parse_partial(to)?);
+    let mut set = vec![];
+    match from {
+        Partial { major: None, .. } => {}
+        Partial { major: Some(major), minor: None, .. } => set.push(comparator(Op::Ge, Version::new(major, 0, 0))),
+        Partial { major: Some(major), minor: Some(minor), patch: None, .. } => set.push(comparator(Op::Ge, Version::new(major, minor, 0))),
+        _ => set.push(comparator(Op::Ge, from.version())),
+    }
+    match to {
+        Partial { major: None, .. } => {}
+        Partial { major: Some(major), minor: None, .. } => set.push(comparator(Op::Lt, lowest(major + 1, 0, 0))),
+        Partial { major: Some(major), minor: Some(minor), patch: None, .. } => set.push(comparator(Op::Lt, lowest(major, minor + 1, 0))),
+        _ => set.push(comparator(Op::Le, to.version())),
+    }
+    Some(set)
+}
+
+fn parse_comparator(token: &str) -> Option<Vec<Comparator>> {
+    let (op, rest) = [("~>", "~"), ("~", "~"), ("^", "^"), (">=", ">="), ("<=", "<="), (">", ">"), ("<", "<"), ("=", "=")]
+        .iter()
+        .find_map(|(prefix, op)| token.strip_prefix(prefix).map(|rest| (*op, rest)))
+        .unwrap_or(("", token));
+    let partial = parse_partial(rest)?;
+    Some(match op {
+        "^" => caret(&partial),
+        "~" => tilde(&partial),
+        ">" => x_range(Op::Gt, &partial),
+        ">=" => x_range(Op::Ge, &partial),
+        "<" => x_range(Op::Lt, &partial),
+        "<=" => x_range(Op::Le, &partial),
+        _ => x_range(Op::Eq, &partial),
+    })
+}
+
+/// `^1.2.3` allows changes that do not modify the left-most non-zero part.
+fn caret(p: &Partial) -> Vec<Comparator> {
+    let upper = match *p {
+        Partial { major: None, .. } => return vec![],
+        Partial { major: Some(major), minor: None, .. } => return vec![comparator(Op::Ge, Version::new(major, 0, 0)), comparator(Op::Lt, lowest(major + 1, 0, 0))],
+        Partial { major: Some(0), minor: Some(minor), patch: None, .. } => return vec![comparator(Op::Ge, Version::new(0, minor, 0)), comparator(Op::Lt, lowest(0, minor + 1, 0))],
+        Partial { major: Some(major), minor: Some(minor), patch: None, .. } => {
+            return vec![comparator(Op::Ge, Version::new(major, minor, 0)), comparator(Op::Lt, lowest(major + 1, 0, 0))];
+        }
+        Partial { major: Some(0), minor: Some(0), patch: Some(patch), .. } => lowest(0, 0, patch + 1),
+        Partial { major: Some(0), minor: Some(minor), .. } => lowest(0, minor + 1, 0),
+        Partial { major: Some(major), .. } => lowest(major + 1, 0, 0),
+    };
+    vec![comparator(Op::Ge, p.version()), comparator(Op::Lt, upper)]
+}
+
+/// `~1.2.3` allows patch-level changes, `~1` allows minor-level changes.
+fn tilde(p: &Partial) -> Vec<Comparator> {
+    match *p {
+        Partial { major: None, .. } => vec![],
+        Partial { major: Some(major), minor: None, .. } => vec![comparator(Op::Ge, Version::new(major, 0, 0)), comparator(Op::Lt, lowest(major + 1, 0, 0))],
+        Partial { major: Some(major), minor: Some(minor), patch: None, .. } => vec![comparator(Op::Ge, Version::new(major, minor, 0)), comparator(Op::Lt, lowest(major, minor + 1, 0))],
+        Partial { major: Some(major), minor: Some(minor), .. } => vec![comparator(Op::Ge, p.version()), comparator(Op::Lt, lowest(major, minor + 1, 0))],
+    }
+}
+
+/// A primitive comparator, where a partial version like `1.x` matches all versions it covers.
+fn x_range(op: Op, p: &Partial) -> Vec<Comparator> {
+    let Some(major) = p.major else {
+        // `>*` and `<*` match nothing
+        return if matches!(op, Op::Gt | Op::Lt) { vec![comparator(Op::Lt, lowest(0, 0, 0))] } else { vec![] };
+    };
+    if p.patch.is_some() {
+        return vec![comparator(op, p.version())];
+    }
+    let minor = p.minor.unwrap_or(0);
+    let next = if p.minor.is_none() { (major + 1, 0) } else { (major, minor + 1) };
+    match op {
+        Op::Gt => vec![comparator(Op::Ge, Version::new(next.0, next.1, 0))],
+        Op::Ge => vec![comparator(Op::Ge, Version::new(major, minor, 0))],
+        Op::Lt => vec![comparator(Op::Lt, lowest(major, minor, 0))],
+        Op::Le => vec![comparator(Op::Lt, lowest(next.0, next.1, 0))],
+        Op::Eq => vec![comparator(Op::Ge, Version::new(major, minor, 0)), comparator(Op::Lt, lowest(next.0, next.1, 0))],
+    }
+}
+
+/// Parse a partial version, prerelease identifiers are only allowed after a numeric patch.
+fn parse_partial(s: &str) -> Option<Partial> {
+    let s = s.trim_start_matches(['v', '=']);
+    let s = s.split_once('+').map_or(s, |(s, _)| s);
+    let (s, pre) = match s.split_once('-') {
+        Some((s, pre)) => (s, pre.split('.').map(parse_prerelease).collect::<Option<Vec<_>>>()?),
+        None => (s, vec![]),
+    };
+    let parts: Vec<&str> = s.split('.').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut numbers = [None; 3];
+    for (i, part) in parts.iter().enumerate() {
+        if matches!(*part, "x" | "X" | "*") {
+            break;
+        }
+        numbers[i] = Some(parse_numeric(part)?);
+    }
+    let [major, minor, patch] = numbers;
+    if !pre.is_empty() && patch.is_none() {
+        return None;
+    }
+    Some(Partial { major, minor, patch, pre })
+}
+
+impl FromStr for Range {
+    type Err = RangeError;
+
+    fn from_str(s: &str) -> Result<Self, RangeError> {
+        Self::parse(s)
+    }
+}
+
+/// Writes the expanded comparators, e.g. `>=1.2.0 <2.0.0-0` for `^1.2.0`.
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, set) in self.set.iter().enumerate() {
+            if i > 0 {
+                f.write_str("||")?;
+            }
+            if set.is_empty() {
+                f.write_str("*")?;
+            }
+            for (j, c) in set.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                write!(f, "{c}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op.as_str(), self.version)
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-')
+}
+
+fn parse_numeric(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0')) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn parse_prerelease(s: &str) -> Option<Prerelease> {
+    if !is_identifier(s) {
+        return None;
+    }
+    if s.bytes().all(|c| c.is_ascii_digit()) {
+        parse_numeric(s).map(Prerelease::Numeric)
+    } else {
+        Some(Prerelease::Alphanumeric(s.to_string()))
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, id) in self.pre.iter().enumerate() {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            match id {
+                Prerelease::Numeric(n) => write!(f, "{n}")?,
+                Prerelease::Alphanumeric(s) => f.write_str(s)?,
+            }
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch)).then_with(|| {
+            // a version without prerelease identifiers has a higher precedence
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Prerelease {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Prerelease {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Numeric(a), Self::Numeric(b)) => a.cmp(b),
+            (Self::Numeric(_), Self::Alphanumeric(_)) => Ordering::Less,
+            (Self::Alphanumeric(_), Self::Numeric(_)) => Ordering::Greater,
+            (Self::Alphanumeric(a), Self::Alphanumeric(b)) => a.cmp(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!("1.2.3", v(" v1.2.3 ").to_string());
+        assert_eq!("1.0.0-beta.1+sha.abc", v("=1.0.0-beta.1+sha.abc").to_string());
+        assert_eq!(vec![Prerelease::Alphanumeric("beta".to_string()), Prerelease::Numeric(1)], v("1.0.0-beta.1").pre);
+        for s in ["", "1", "1.2", "1.2.3.4", "01.2.3", "1.2.x", "1.2.3-", "1.2.3-a..b", "1.2.3+"] {
+            assert!(Version::parse(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn compare_versions() {
+        // https://semver.org/#spec-item-11
+        let ordered = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.10.0", "2.0.0"];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(v("1.0.0+a"), v("1.0.0+b"));
+    }
+
+    #[test]
+    fn parse_ranges() {
+        // https://github.com/npm/node-semver/blob/v7.6.3/test/fixtures/range-parse.js
+        for (range, expected) in [
+            ("1.0.0 - 2.0.0", ">=1.0.0 <=2.0.0"),
+            ("1 - 2", ">=1.0.0 <3.0.0-0"),
+            ("1.0 - 2.0", ">=1.0.0 <2.1.0-0"),
+            ("1.0.0", "1.0.0"),
+            (">=*", "*"),
+            ("", "*"),
+            ("*", "*"),
+            (">= 1.0.0", ">=1.0.0"),
+            ("> 1.0.0", ">1.0.0"),
+            ("<=   2.0.0", "<=2.0.0"),
+            ("0.1.20 || 1.2.4", "0.1.20||1.2.4"),
+            (">=0.2.3 || <0.0.1", ">=0.2.3||<0.0.1"),
+            ("2.x.x", ">=2.0.0 <3.0.0-0"),
+            ("1.2.x || 2.x", ">=1.2.0 <1.3.0-0||>=2.0.0 <3.0.0-0"),
+            ("x", "*"),
+            ("~2.4", ">=2.4.0 <2.5.0-0"),
+            ("~>3.2.1", ">=3.2.1 <3.3.0-0"),
+            ("~1", ">=1.0.0 <2.0.0-0"),
+            ("~ 1.0.3", ">=1.0.3 <1.1.0-0"),
+            ("~1.2.3-beta.2", ">=1.2.3-beta.2 <1.3.0-0"),
+            (">1", ">=2.0.0"),
+            (">1.2", ">=1.3.0"),
+            ("<1.2", "<1.2.0-0"),
+            ("<=1.2", "<1.3.0-0"),
+            ("<x", "<0.0.0-0"),
+            ("^1.2.3", ">=1.2.3 <2.0.0-0"),
+            ("^0.1.2", ">=0.1.2 <0.2.0-0"),
+            ("^0.0.1", ">=0.0.1 <0.0.2-0"),
+            ("^0.0", ">=0.0.0 <0.1.0-0"),
+            ("^1.2", ">=1.2.0 <2.0.0-0"),
+            ("^0.x", ">=0.0.0 <1.0.0-0"),
+            ("^1.2.3-beta.4", ">=1.2.3-beta.4 <2.0.0-0"),
+            ("v1.2.3 =1.2.4", "1.2.3 1.2.4"),
+        ] {
+            assert_eq!(expected, Range::parse(range).unwrap().to_string(), "{range}");
+        }
+        for range in ["latest", "^1.2.3.4", "1.2.x-beta", ">=a", "next || 1"] {
+            assert_eq!(Err(RangeError { input: range.to_string() }), Range::parse(range), "{range}");
+        }
+    }
+
+    #[test]
+    fn satisfies_ranges() {
+        // https://github.com/npm/node-semver/blob/v7.6.3/test/fixtures/range-include.js
+        for (range, version) in [
+            ("1.0.0 - 2.0.0", "1.2.3"),
+            ("^1.2.3+build", "1.2.3"),
+            ("^1.2.3+build", "1.3.0"),
+            ("1.2.3-pre+asdf - 2.4.3-pre+asdf", "1.2.3-pre.2"),
+            ("1.2.3-pre+asdf - 2.4.3-pre+asdf", "2.4.3-alpha"),
+            ("*", "1.2.3"),
+            (">=1.0.0", "1.1.0"),
+            ("0.1.20 || 1.2.4", "1.2.4"),
+            ("||", "1.3.4"),
+            ("2.x.x", "2.1.3"),
+            ("~2.4", "2.4.5"),
+            ("~> 1", "1.2.3"),
+            ("<1.2", "1.1.1"),
+            ("~v0.5.4-pre", "0.5.5"),
+            ("~v0.5.4-pre", "0.5.4"),
+            (">=1.2", "1.2.8"),
+            ("^1.2.3", "1.8.1"),
+            ("^0.1.2", "0.1.2"),
+            ("^1.2.0-alpha", "1.2.0-pre"),
+            ("^0.0.1-alpha", "0.0.1-beta"),
+            ("^x", "1.2.3"),
+            (">= 1.0.0 < 2", "1.9.9"),
+        ] {
+            assert!(Range::parse(range).unwrap().satisfies(&v(version)), "{version} satisfies {range}");
+        }
+        // https://github.com/npm/node-semver/blob/v7.6.3/test/fixtures/range-exclude.js
+        for (range, version) in [
+            ("1.0.0 - 2.0.0", "2.2.3"),
+            ("1.2.3+asdf - 2.4.3+asdf", "1.2.3-pre.2"),
+            ("^1.2.3+build", "2.0.0"),
+            ("^1.2.3", "1.2.3-pre"),
+            ("^1.2", "1.2.0-pre"),
+            (">1.2", "1.3.0-beta"),
+            ("<=1.2.3", "1.2.3-beta"),
+            ("^1.2.3", "1.2.3-beta"),
+            ("*", "1.0.0-beta"),
+            ("^2.0.0", "1.9.0"),
+            ("~1.2.1 >=1.2.3", "1.2.2"),
+            ("<x", "0.0.0"),
+            ("^0.0.1", "0.0.2"),
+            ("^1.2.0-alpha", "1.3.0-beta"),
+        ] {
+            assert!(!Range::parse(range).unwrap().satisfies(&v(version)), "{version} does not satisfy {range}");
+        }
+    }
+}
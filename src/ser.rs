@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::io;
+use std::rc::Rc;
+
+use serde::ser::{Impossible, SerializeMap, SerializeStruct, SerializeStructVariant};
+use serde::{ser, Serialize, Serializer};
+
+use crate::parser::Value;
+use crate::stringify::stringify_without_header;
+
+/// Serialization error.
+#[derive(Debug)]
+pub enum Error {
+    /// Writing the output failed.
+    Io(io::Error),
+    /// The value cannot be represented in yarn lockfile syntax.
+    Message(String),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Message(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Message(msg) => write!(f, "Serialization error: {msg}"),
+        }
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Serialize the value as yarn lockfile syntax into the writer.
+///
+/// The output follows [`crate::stringify::stringify_without_header`], so the lockfile header is
+/// not written. `None` fields are skipped, and sequences are not supported by the syntax.
+///
+/// # Errors
+/// - [`Error::Message`]: When the value is not a map or struct, or contains a sequence or a
+///   negative, fractional or too large number
+/// - [`Error::Io`]: When writing failed
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(mut writer: W, value: &T) -> Result<(), Error> {
+    writer.write_all(to_string(value)?.as_bytes())?;
+    Ok(())
+}
+
+/// Serialize the value as yarn lockfile syntax, see [`to_writer`].
+///
+/// # Errors
+/// - [`Error::Message`]: When the value is not a map or struct, or contains a sequence or a
+///   negative, fractional or too large number
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    match to_value(value)? {
+        v @ Value::Object(_) => Ok(stringify_without_header(&v)),
+        _ => Err(Error::Message("top-level value must be a map or struct".to_string())),
+    }
+}
+
+/// Convert the value into a [`Value`].
+///
+/// # Errors
+/// - [`Error::Message`]: When the value contains a sequence, bytes, or a negative, fractional or
+///   too large number
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, Error> {
+    value.serialize(ValueSerializer)
+}
+
+impl Serialize for Value {
+    #[allow(clippy::cast_possible_truncation)]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Number(n) if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 => serializer.serialize_i64(*n as i64),
+            Self::Number(n) => serializer.serialize_f64(*n),
+            Self::Boolean(b) => serializer.serialize_bool(*b),
+            Self::Object(map) => serializer.collect_map(map),
+            Self::Null => serializer.serialize_unit(),
+        }
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(Rc::new(s.to_string()))
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Message(format!("{what} are not supported by the lockfile syntax"))
+}
+
+/// The largest integer a number is read back as without losing precision, like
+/// `Number.MAX_SAFE_INTEGER`.
+const MAX_NUMBER: u64 = (1 << 53) - 1;
+
+/// The lexer only reads non-negative integers as numbers: `-1` and `-0` would be read back as
+/// strings, `1.5` cannot be read back at all, and integers above [`MAX_NUMBER`] lose precision.
+fn unsupported_number(n: impl fmt::Display) -> Error {
+    Error::Message(format!("{n} is not supported by the lockfile syntax, only non-negative integers up to {MAX_NUMBER} are"))
+}
+
+struct ValueSerializer;
+
+#[allow(clippy::cast_precision_loss)]
+impl Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = Impossible<Value, Error>;
+    type SerializeTuple = Impossible<Value, Error>;
+    type SerializeTupleStruct = Impossible<Value, Error>;
+    type SerializeTupleVariant = Impossible<Value, Error>;
+    type SerializeMap = ObjectSerializer;
+    type SerializeStruct = ObjectSerializer;
+    type SerializeStructVariant = ObjectSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        u64::try_from(v).map_or_else(|_| Err(unsupported_number(v)), |v| self.serialize_u64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        if v <= MAX_NUMBER {
+            Ok(Value::Number(v as f64))
+        } else {
+            Err(unsupported_number(v))
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        if v.fract() == 0.0 && !v.is_sign_negative() && v <= MAX_NUMBER as f64 {
+            Ok(Value::Number(v))
+        } else {
+            Err(unsupported_number(v))
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::String(Rc::new(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(string(v))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Value, Error> {
+        Err(unsupported("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Value, Error> {
+        Ok(string(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Value, Error> {
+        Ok(Value::Object(HashMap::from([(variant.to_string(), value.serialize(self)?)])))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("sequences"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("tuples"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("tuple structs"))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("tuple variants"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<ObjectSerializer, Error> {
+        Ok(ObjectSerializer { map: HashMap::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ObjectSerializer, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<ObjectSerializer, Error> {
+        Ok(ObjectSerializer { map: HashMap::new(), key: None, variant: Some(variant) })
+    }
+}
+
+struct ObjectSerializer {
+    map: HashMap<String, Value>,
+    key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl ObjectSerializer {
+    fn finish(self) -> Value {
+        let object = Value::Object(self.map);
+        match self.variant {
+            Some(variant) => Value::Object(HashMap::from([(variant.to_string(), object)])),
+            None => object,
+        }
+    }
+}
+
+impl SerializeMap for ObjectSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(ValueSerializer)? {
+            Value::String(s) => self.key = Some(s.to_string()),
+            Value::Number(n) => self.key = Some(n.to_string()),
+            Value::Boolean(b) => self.key = Some(b.to_string()),
+            _ => return Err(Error::Message("map keys must be strings".to_string())),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| Error::Message("value without a key".to_string()))?;
+        self.map.insert(key, value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStruct for ObjectSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.map.insert(key.to_string(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeStructVariant for ObjectSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::de::from_slice;
+    use crate::parser::parse;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry {
+        version: &'static str,
+        resolved: Option<&'static str>,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        dependencies: BTreeMap<&'static str, &'static str>,
+    }
+
+    #[test]
+    fn serialize_entries() {
+        let lockfile = BTreeMap::from([
+            ("a@^1.0.0", Entry { version: "1.0.0", resolved: Some("https://example.com/a.tgz"), dependencies: BTreeMap::from([("b", "^2.0.0")]) }),
+            ("b@^2.0.0", Entry { version: "2.0.0", resolved: None, dependencies: BTreeMap::new() }),
+        ]);
+        let mut out = vec![];
+        to_writer(&mut out, &lockfile).unwrap();
+        assert_eq!(
+            "a@^1.0.0:\n  version \"1.0.0\"\n  resolved \"https://example.com/a.tgz\"\n  dependencies:\n    b \"^2.0.0\"\n\nb@^2.0.0:\n  version \"2.0.0\"\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Mode {
+        Offline,
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        offline: bool,
+        retries: u32,
+        mode: Mode,
+    }
+
+    #[test]
+    fn serialize_scalars() {
+        let config = Config { offline: true, retries: 3, mode: Mode::Offline };
+        assert_eq!("mode offline\noffline true\nretries 3", to_string(&config).unwrap());
+    }
+
+    #[test]
+    fn serialize_errors() {
+        assert!(matches!(to_string("a"), Err(Error::Message(_))));
+        assert!(matches!(to_string(&BTreeMap::from([("a", vec![1])])), Err(Error::Message(_))));
+        assert!(matches!(to_string(&BTreeMap::from([("a", -1)])), Err(Error::Message(_))));
+        assert!(matches!(to_string(&BTreeMap::from([("a", 1.5)])), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn number_round_trip() {
+        let numbers = BTreeMap::from([("a", 0.0), ("b", 3.0), ("c", 9_007_199_254_740_991.0)]);
+        let mut out = vec![];
+        to_writer(&mut out, &numbers).unwrap();
+        assert_eq!(numbers, from_slice::<BTreeMap<&str, f64>>(&out).unwrap());
+        let numbers = BTreeMap::from([("a", 0_i64), ("b", i64::from(u32::MAX))]);
+        let mut out = vec![];
+        to_writer(&mut out, &numbers).unwrap();
+        assert_eq!(numbers, from_slice::<BTreeMap<&str, i64>>(&out).unwrap());
+        let numbers = BTreeMap::from([("a", MAX_NUMBER)]);
+        assert_eq!(numbers, from_slice::<BTreeMap<&str, u64>>(to_string(&numbers).unwrap().as_bytes()).unwrap());
+
+        assert!(matches!(to_string(&BTreeMap::from([("a", u64::MAX)])), Err(Error::Message(_))));
+        assert!(matches!(to_string(&BTreeMap::from([("a", (1_u64 << 53) + 1)])), Err(Error::Message(_))));
+        assert!(matches!(to_string(&BTreeMap::from([("a", -0.0)])), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let value = parse(include_bytes!("test.lock.1")).unwrap();
+        assert_eq!(value, to_value(&value).unwrap());
+        let value: Value = from_slice(include_bytes!("test.lock.1")).unwrap();
+        assert_eq!(parse(include_bytes!("test.lock.1")).unwrap(), value);
+    }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+
+use crate::parser::Value;
+
+const HEADER: &str = "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile v1\n\n\n";
+
+/// Stringify the value into yarn lock data, including the autogenerated header.
+///
+/// Translated from [https://github.com/yarnpkg/yarn/blob/master/src/lockfile/stringify.js](https://github.com/yarnpkg/yarn/blob/7cafa512a777048ce0b666080a24e80aae3d66a9/src/lockfile/stringify.js#L96)
+/// Keep code-style consistent with the original code.
+///
+/// Top-level keys with equal object values are joined into a single comma-separated key line,
+/// the same way yarn writes descriptors that resolve to the same package.
+#[must_use]
+pub fn stringify(value: &Value) -> String {
+    let mut out = String::from(HEADER);
+    out.push_str(&stringify_without_header(value));
+    out
+}
+
+/// Stringify the value into yarn lock data, without the autogenerated header.
+///
+/// See [`stringify`].
+#[must_use]
+pub fn stringify_without_header(value: &Value) -> String {
+    match value {
+        Value::Object(map) => stringify_object(map, "", true),
+        _ => String::new(),
+    }
+}
+
+fn stringify_object(obj: &HashMap<String, Value>, indent: &str, top_level: bool) -> String {
+    let mut keys: Vec<&String> = obj.keys().collect();
+    keys.sort_by(|a, b| priority_then_alpha_sort(a, b));
+
+    // only top-level values can share a key line, nested objects are never written that way by yarn
+    let mut same_values: HashMap<u64, Vec<usize>> = HashMap::new();
+    if top_level {
+        for (i, key) in keys.iter().enumerate() {
+            if let v @ Value::Object(_) = &obj[*key] {
+                same_values.entry(fingerprint(v)).or_default().push(i);
+            }
+        }
+    }
+
+    let mut added = vec![false; keys.len()];
+    let mut lines: Vec<String> = vec![];
+    for (i, key) in keys.iter().enumerate() {
+        let val = &obj[*key];
+        if matches!(val, Value::Null) || added[i] {
+            continue;
+        }
+        let mut val_keys = vec![i];
+        if top_level && matches!(val, Value::Object(_)) {
+            for &j in &same_values[&fingerprint(val)] {
+                if j > i && !added[j] && &obj[keys[j]] == val {
+                    val_keys.push(j);
+                }
+            }
+        }
+        let mut names: Vec<&str> = val_keys.iter().map(|&j| keys[j].as_str()).collect();
+        names.sort_by(|a, b| sort_alpha(a, b));
+        let key_line = names.iter().map(|s| maybe_wrap(s)).collect::<Vec<_>>().join(", ");
+
+        match val {
+            Value::String(s) => lines.push(format!("{key_line} {}", maybe_wrap(s))),
+            Value::Number(n) => lines.push(format!("{key_line} {n}")),
+            Value::Boolean(b) => lines.push(format!("{key_line} {b}")),
+            Value::Object(map) => {
+                let nested = stringify_object(map, &format!("{indent}  "), false);
+                lines.push(format!("{key_line}:\n{nested}{}", if top_level { "\n" } else { "" }));
+            }
+            Value::Null => {}
+        }
+        for j in val_keys {
+            added[j] = true;
+        }
+    }
+    format!("{indent}{}", lines.join(&format!("\n{indent}")))
+}
+
+fn priority(key: &str) -> Option<u8> {
+    match key {
+        "name" => Some(1),
+        "version" => Some(2),
+        "uid" => Some(3),
+        "resolved" => Some(4),
+        "integrity" => Some(5),
+        "registry" => Some(6),
+        "dependencies" => Some(7),
+        _ => None,
+    }
+}
+
+pub(crate) fn priority_then_alpha_sort(a: &str, b: &str) -> Ordering {
+    match (priority(a), priority(b)) {
+        (None, None) => sort_alpha(a, b),
+        (pa, pb) => pa.unwrap_or(100).cmp(&pb.unwrap_or(100)),
+    }
+}
+
+/// Compare strings by UTF-16 code units, like `sortAlpha` in yarn's `misc.js`.
+pub(crate) fn sort_alpha(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+fn should_wrap_key(s: &str) -> bool {
+    s.starts_with("true")
+        || s.starts_with("false")
+        || s.chars().any(|c| matches!(c, ':' | '\\' | '"' | ',' | '[' | ']' | '\u{feff}') || c.is_whitespace())
+        || !s.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// Quote the string when yarn would, see `maybeWrap` in yarn's `stringify.js`.
+pub(crate) fn maybe_wrap(s: &str) -> String {
+    if should_wrap_key(s) {
+        json_quote(s)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Quote the string like `JSON.stringify` does.
+pub(crate) fn json_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{0008}' => out.push_str("\\b"),
+            '\u{000c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => _ = write!(out, "\\u{:04x}", c as u32),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn fingerprint(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match value {
+        Value::String(s) => s.hash(&mut hasher),
+        Value::Number(n) => n.to_bits().hash(&mut hasher),
+        Value::Boolean(b) => b.hash(&mut hasher),
+        Value::Object(map) => {
+            // keep it independent of the iteration order
+            let sum = map.iter().fold(0u64, |acc, (k, v)| {
+                let mut h = DefaultHasher::new();
+                k.hash(&mut h);
+                fingerprint(v).hash(&mut h);
+                acc.wrapping_add(h.finish())
+            });
+            sum.hash(&mut hasher);
+        }
+        Value::Null => {}
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::parser::parse;
+
+    #[test]
+    fn stringify1() {
+        let input = include_str!("test.lock.1");
+        assert_eq!(input, stringify(&parse(input.as_bytes()).unwrap()));
+    }
+
+    #[test]
+    fn stringify2() {
+        let input = include_str!("test.lock.2");
+        assert_eq!(input, stringify(&parse(input.as_bytes()).unwrap()));
+    }
+
+    #[test]
+    fn test_maybe_wrap() {
+        assert_eq!("lodash", maybe_wrap("lodash"));
+        assert_eq!("sha512-abc+/=", maybe_wrap("sha512-abc+/="));
+        assert_eq!("\"1.0.0\"", maybe_wrap("1.0.0"));
+        assert_eq!("\"@babel/core@^7.0.0\"", maybe_wrap("@babel/core@^7.0.0"));
+        assert_eq!("\"trueish\"", maybe_wrap("trueish"));
+        assert_eq!("\"a b\"", maybe_wrap("a b"));
+        assert_eq!("\"https://x\"", maybe_wrap("https://x"));
+        assert_eq!("\"\"", maybe_wrap(""));
+        assert_eq!("\"a\\\"\\n\\u0001\"", maybe_wrap("a\"\n\u{1}"));
+    }
+
+    #[test]
+    fn stringify_priority_order_and_scalars() {
+        let mut entry = HashMap::new();
+        entry.insert("zzz".to_string(), Value::Boolean(true));
+        entry.insert("aaa".to_string(), Value::Number(1.0));
+        entry.insert("dependencies".to_string(), Value::Object(HashMap::new()));
+        entry.insert("version".to_string(), Value::String(Rc::new("1.0.0".to_string())));
+        entry.insert("name".to_string(), Value::String(Rc::new("b".to_string())));
+        entry.insert("skipped".to_string(), Value::Null);
+        let mut map = HashMap::new();
+        map.insert("a@^1.0.0".to_string(), Value::Object(entry.clone()));
+        map.insert("a@1".to_string(), Value::Object(entry));
+        assert_eq!(
+            "a@1, a@^1.0.0:\n  name b\n  version \"1.0.0\"\n  dependencies:\n    \n  aaa 1\n  zzz true\n",
+            stringify_without_header(&Value::Object(map))
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Token<'t> {
     Bool(bool),
     String(&'t [u8]),
@@ -20,7 +20,7 @@ macro_rules! u8quote {
         ($v: expr) => {std::str::from_utf8($v).unwrap()};
     }
 
-impl<'t> Debug for Token<'t> {
+impl Debug for Token<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Token::Bool(b) => write!(f, "Bool({b})"),
@@ -38,7 +38,39 @@ impl<'t> Debug for Token<'t> {
 }
 
 
-#[derive(Debug)]
+/// The kind of a [`Token`], without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Bool,
+    String,
+    Number,
+    Indent,
+    Comment,
+    Eof,
+    Colon,
+    NewLine,
+    Invalid,
+    Comma,
+}
+
+impl From<&Token<'_>> for TokenKind {
+    fn from(token: &Token<'_>) -> Self {
+        match token {
+            Token::Bool(_) => Self::Bool,
+            Token::String(_) => Self::String,
+            Token::Number(_) => Self::Number,
+            Token::Indent(_) => Self::Indent,
+            Token::Comment(_) => Self::Comment,
+            Token::Eof => Self::Eof,
+            Token::Colon => Self::Colon,
+            Token::NewLine => Self::NewLine,
+            Token::Invalid => Self::Invalid,
+            Token::Comma => Self::Comma,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 #[derive(PartialEq)]
 pub struct TokenWrapper<'t> {
     pub col: i32,
@@ -46,3 +78,12 @@ pub struct TokenWrapper<'t> {
     pub token: Token<'t>,
 }
 
+
+/// A byte range in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    /// The offset of the first byte.
+    pub start: usize,
+    /// The offset after the last byte.
+    pub end: usize,
+}
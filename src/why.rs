@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+use crate::descriptor::Descriptor;
+use crate::graph::DependencyGraph;
+use crate::manifest::Manifest;
+use crate::semver::{Range, Version};
+
+/// Which dependency paths [`DependencyGraph::why`] returns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WhyMode {
+    /// The shortest path to every matching node.
+    #[default]
+    Shortest,
+    /// Every path without cycles to every matching node.
+    All,
+}
+
+impl DependencyGraph<'_> {
+    /// Explain why a package is in the lockfile, like `yarn why`.
+    ///
+    /// The query is a package name, e.g. `@babel/core`, or a name and a version or range, e.g.
+    /// `@babel/core@7.24.0`. Returns the dependency paths from the dependencies of the root
+    /// manifest to the matching nodes, as node indexes starting at a direct dependency. At most
+    /// `limit` paths are returned, ordered by length in [`WhyMode::Shortest`] mode.
+    ///
+    /// In [`WhyMode::All`] mode the number of paths can grow exponentially with the graph size,
+    /// use a `limit` on large lockfiles.
+    #[must_use]
+    pub fn why(&self, manifest: &Manifest, query: &str, mode: WhyMode, limit: Option<usize>) -> Vec<Vec<usize>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let query = Descriptor::parse(query);
+        let range = if query.has_version { Range::parse(&query.range).ok() } else { None };
+        let targets: Vec<bool> = (0..self.len()).map(|id| self.matches(id, &query, range.as_ref())).collect();
+        let mut roots: Vec<usize> = vec![];
+        for (name, range) in manifest.installed_dependencies() {
+            if let Some(id) = self.resolve(name, range).filter(|id| !roots.contains(id)) {
+                roots.push(id);
+            }
+        }
+        let mut paths = match mode {
+            WhyMode::Shortest => self.shortest_paths(&roots, &targets),
+            WhyMode::All => {
+                let mut paths = vec![];
+                for &root in &roots {
+                    self.all_paths(&mut vec![root], &targets, limit, &mut paths);
+                }
+                paths
+            }
+        };
+        paths.truncate(limit);
+        paths
+    }
+
+    /// Whether the node matches the query, `range` being the parsed range of the query.
+    fn matches(&self, id: usize, query: &Descriptor, range: Option<&Range>) -> bool {
+        let Some(node) = self.node(id) else {
+            return false;
+        };
+        let effective_name = Descriptor::parse(node.descriptors[0]).effective_name();
+        if node.name != query.name && effective_name != query.name {
+            return false;
+        }
+        if !query.has_version || node.entry.version == query.range {
+            return true;
+        }
+        match (range, Version::parse(&node.entry.version)) {
+            (Some(range), Ok(version)) => range.satisfies(&version),
+            _ => false,
+        }
+    }
+
+    /// Breadth-first search from all roots at once, so every node is reached by a shortest path.
+    fn shortest_paths(&self, roots: &[usize], targets: &[bool]) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.len()];
+        let mut parents: Vec<Option<usize>> = vec![None; self.len()];
+        let mut queue = VecDeque::new();
+        for &root in roots {
+            visited[root] = true;
+            queue.push_back(root);
+        }
+        let mut paths = vec![];
+        while let Some(id) = queue.pop_front() {
+            if targets[id] {
+                let mut path = vec![id];
+                while let Some(parent) = parents[path[path.len() - 1]] {
+                    path.push(parent);
+                }
+                path.reverse();
+                paths.push(path);
+            }
+            for edge in self.dependencies(id) {
+                if !visited[edge.to] {
+                    visited[edge.to] = true;
+                    parents[edge.to] = Some(id);
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        paths
+    }
+
+    fn all_paths(&self, path: &mut Vec<usize>, targets: &[bool], limit: usize, paths: &mut Vec<Vec<usize>>) {
+        if paths.len() >= limit {
+            return;
+        }
+        let id = path[path.len() - 1];
+        if targets[id] {
+            paths.push(path.clone());
+        }
+        for edge in self.dependencies(id) {
+            if !path.contains(&edge.to) {
+                path.push(edge.to);
+                self.all_paths(path, targets, limit, paths);
+                path.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::Lockfile;
+
+    fn names(graph: &DependencyGraph, paths: &[Vec<usize>]) -> Vec<String> {
+        paths.iter().map(|path| path.iter().map(|&id| graph.node(id).unwrap().descriptors[0]).collect::<Vec<_>>().join(" > ")).collect()
+    }
+
+    #[test]
+    fn why_paths() {
+        let lockfile = Lockfile::parse(include_bytes!("test.lock.1")).unwrap();
+        let graph = DependencyGraph::new(&lockfile);
+        let manifest = Manifest::parse(br#"{"dependencies": {"@jridgewell/source-map": "^0.3.3", "@jridgewell/gen-mapping": "^0.3.5"}}"#).unwrap();
+
+        let paths = graph.why(&manifest, "@jridgewell/sourcemap-codec", WhyMode::Shortest, None);
+        assert_eq!(vec!["@jridgewell/gen-mapping@^0.3.5 > @jridgewell/sourcemap-codec@^1.4.10"], names(&graph, &paths));
+
+        let paths = graph.why(&manifest, "@jridgewell/sourcemap-codec@1.4.15", WhyMode::All, None);
+        assert_eq!(
+            vec![
+                "@jridgewell/gen-mapping@^0.3.5 > @jridgewell/sourcemap-codec@^1.4.10",
+                "@jridgewell/gen-mapping@^0.3.5 > @jridgewell/trace-mapping@^0.3.20 > @jridgewell/sourcemap-codec@^1.4.10",
+                "@jridgewell/source-map@^0.3.3 > @jridgewell/gen-mapping@^0.3.5 > @jridgewell/sourcemap-codec@^1.4.10",
+                "@jridgewell/source-map@^0.3.3 > @jridgewell/gen-mapping@^0.3.5 > @jridgewell/trace-mapping@^0.3.20 > @jridgewell/sourcemap-codec@^1.4.10",
+                "@jridgewell/source-map@^0.3.3 > @jridgewell/trace-mapping@^0.3.20 > @jridgewell/sourcemap-codec@^1.4.10",
+            ],
+            {
+                let mut names = names(&graph, &paths);
+                names.sort();
+                names
+            }
+        );
+        assert_eq!(2, graph.why(&manifest, "@jridgewell/sourcemap-codec@^1.4.0", WhyMode::All, Some(2)).len());
+        assert!(graph.why(&manifest, "@jridgewell/sourcemap-codec@2.0.0", WhyMode::All, None).is_empty());
+        assert!(graph.why(&manifest, "lodash", WhyMode::Shortest, None).is_empty());
+        assert!(graph.why(&Manifest::default(), "@jridgewell/sourcemap-codec", WhyMode::Shortest, None).is_empty());
+    }
+}